@@ -1,46 +1,250 @@
-use version_it_core::{VersionInfo, Config};
-use super::output::{output_success, output_error};
-use super::git_ops::{git_commit_changes, git_create_tag};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use version_it_core::{Scheme, VersionInfo, VersionType, Config, VersionItError, VersionSource};
+use version_it_core::utils::{checksum_path, slugify, write_atomic, write_checksum};
+use version_it_core::package::npm_lockfile;
+use version_it_core::templates::{render_tag_message, render_version_format};
+use crate::git_ops::{git_commit_changes, git_copy_tag, git_create_tag, git_delete_tag, git_commit_of, git_list_tags, git_remote_branches_containing_head, DEFAULT_GIT_RETRIES};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct BumpOptions {
     pub version: Option<String>,
+    /// File to read the current version from (unless `version` is set) and to write
+    /// the new version to, in place of the config's version file.
+    pub version_file: Option<PathBuf>,
+    /// Extra files the new version is written to, besides the version file.
+    pub write_to: Vec<PathBuf>,
     pub bump: String,
+    /// Bump the component at this index (0 for major) instead of `bump`; numeric and
+    /// build schemes only.
+    pub bump_index: Option<usize>,
     pub scheme: Option<String>,
     pub channel: Option<String>,
+    pub bump_by: Option<u64>,
+    pub set_major: Option<u64>,
+    pub set_minor: Option<u64>,
+    pub set_patch: Option<u64>,
+    pub pre: Option<String>,
+    pub build: Option<String>,
+    /// Append the snapshot suffix (`snapshot-suffix`, default `-SNAPSHOT`) to the new
+    /// version; a suffix on the current version is dropped before bumping.
+    pub snapshot: bool,
+    /// Release the current snapshot: `1.2.3-SNAPSHOT` becomes `1.2.3` without bumping.
+    /// Versions without the suffix are bumped as usual.
+    pub release: bool,
+    pub respect_branch: bool,
+    /// Write the new version even if it is lower than the current one.
+    pub allow_downgrade: bool,
+    /// With `bump: "auto"`, fail instead of warning when the version file and latest tag differ.
+    pub require_tag_match: bool,
+    /// With `bump: "auto"`, analyze the commits after this ref instead of the latest version tag.
+    pub since: Option<String>,
+    /// With `bump: "auto"`, the highest bump allowed, overriding `max-auto-bump`.
+    pub max_bump: Option<String>,
+    /// With `bump: "auto"`, only analyze first-parent commits (`commit-analysis-first-parent`).
+    pub first_parent: bool,
     pub create_tag: bool,
+    /// Handlebars template for the tag annotation (default `Version {{version}}`).
+    pub tag_message: Option<String>,
+    /// Create a lightweight tag without annotation; takes precedence over `tag_message`.
+    pub lightweight: bool,
     pub commit: bool,
+    /// Skip git hooks when committing.
+    pub no_verify: bool,
+    /// Fold the changes into the last commit instead of creating one. An existing tag
+    /// of the new version on that commit is moved to the amended commit.
+    pub amend: bool,
+    /// Amend even when the last commit was already pushed, and move an existing tag of
+    /// the new version from another commit.
+    pub force: bool,
+    /// File to hold an exclusive lock on while reading, writing, committing and
+    /// tagging, so concurrent runs don't bump the same version twice.
+    pub lock: Option<PathBuf>,
+    /// Seconds to wait for `lock` (default 60).
+    pub lock_timeout: Option<u64>,
     pub dry_run: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct AutoBumpOptions {
     pub create_tag: bool,
+    /// Handlebars template for the tag annotation (default `Version {{version}}`).
+    pub tag_message: Option<String>,
+    /// Create a lightweight tag without annotation.
+    pub lightweight: bool,
     pub commit: bool,
+    /// Skip git hooks when committing.
+    pub no_verify: bool,
+    /// Fold the changes into the last commit and move its tag.
+    pub amend: bool,
+    /// Amend even when the last commit was already pushed or the tag is elsewhere.
+    pub force: bool,
     pub dry_run: bool,
+    /// Report how each commit was classified (always done under dry run).
+    pub explain: bool,
+    /// Fail instead of warning when the version file and latest tag differ.
+    pub require_tag_match: bool,
+    /// Analyze the commits after this ref instead of the latest version tag.
+    pub since: Option<String>,
+    /// The highest bump allowed, overriding `max-auto-bump`.
+    pub max_bump: Option<String>,
+    /// Only analyze first-parent commits (`commit-analysis-first-parent`).
+    pub first_parent: bool,
+    /// Only determine the bump type (`check_auto_bump`); nothing is written.
+    pub check: bool,
+    /// File to hold an exclusive lock on during the bump.
+    pub lock: Option<PathBuf>,
+    /// Seconds to wait for `lock` (default 60).
+    pub lock_timeout: Option<u64>,
 }
 
-#[derive(Debug)]
-pub struct CommandContext {
-    pub config: Option<Config>,
-    pub structured_output: bool,
+/// The files and git operations of a bump by kind, for machine-readable dry runs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BumpPlan {
+    /// Files the new version is written to.
+    pub version_files: Vec<String>,
+    /// Header files generated from templates.
+    pub headers: Vec<String>,
+    /// Package files (and npm lockfiles) whose version or dependency is updated.
+    pub package_files: Vec<String>,
+    /// Changelogs that get the new release, one per exporter.
+    pub changelogs: Vec<String>,
+    pub commit: bool,
+    /// Whether the commit amends the last one instead of creating a new one.
+    pub amend: bool,
+    /// Tag to create, if any.
+    pub tag: Option<String>,
+    /// Whether the tag is lightweight rather than annotated.
+    pub lightweight_tag: bool,
+    /// Checksum files written next to the version files and headers (`emit-checksum`).
+    pub checksums: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct RetagOptions {
+    /// Handlebars template for the new tag names (default `{{version}}`), e.g. `v{{version}}`.
+    pub format: Option<String>,
+    /// Delete each old tag after creating its replacement.
+    pub delete_old: bool,
+    /// Actually touch tags; otherwise the renames are only planned.
+    pub apply: bool,
+}
+
+/// A version tag and the name it is (or would be) re-created under.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagRename {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RetagOutcome {
+    /// Whether the renames were applied rather than planned.
+    pub applied: bool,
+    pub renames: Vec<TagRename>,
+    /// Renames skipped because a tag with the new name already exists.
+    pub conflicts: Vec<TagRename>,
+}
+
+/// Result of a bump that was applied, or planned under dry run.
+#[derive(Debug, Clone)]
+pub struct BumpResult {
+    pub previous_version: String,
+    pub version: String,
+    pub scheme: String,
+    pub channel: Option<String>,
+    pub bump_type: String,
+    pub dry_run: bool,
+    /// Descriptions of the file and git operations performed, or planned under dry run.
+    pub operations: Vec<String>,
+    /// The same operations grouped by kind.
+    pub plan: BumpPlan,
+    /// Whether a commit was created (false under dry run or when nothing changed).
+    pub committed: bool,
+    /// Whether a tag was created (false under dry run).
+    pub tagged: bool,
+    /// Whether tagging was skipped because `commit` found no changes to commit.
+    pub nothing_to_tag: bool,
+    /// Version file contents and latest version tag, when an auto bump found them out of sync.
+    pub divergence: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum BumpOutcome {
+    /// The current branch is not listed in `run-on-branches`.
+    Skipped { branch: String },
+    /// `--bump auto` found no commit since the last version tag that warrants a bump.
+    NoBumpNeeded,
+    Bumped(Box<BumpResult>),
+}
+
+#[derive(Debug, Clone)]
+pub struct NextOutcome {
+    pub previous_version: String,
+    pub version: String,
+    pub scheme: String,
+    pub channel: Option<String>,
 }
 
-pub fn get_version_info_with_scheme(version: Option<String>, config: &Option<Config>, scheme_override: Option<String>, channel_override: Option<String>) -> Result<VersionInfo, String> {
-    let version_str = version.or_else(|| config.as_ref().and_then(|c| c.get_current_version().ok()));
+#[derive(Debug, Clone)]
+pub enum AutoBumpOutcome {
+    /// No commit since the last version tag warrants a bump.
+    NoBumpNeeded,
+    Bumped(Box<BumpResult>),
+}
+
+pub fn get_version_info_with_scheme(version: Option<String>, config: Option<&Config>, scheme_override: Option<String>, channel_override: Option<String>) -> Result<VersionInfo, VersionItError> {
+    let channel = channel_override.or_else(|| config.and_then(|c| c.channel.clone()));
+    // --version, then the config's version-resolution-order
+    let version_str = match (version, config) {
+        (Some(version), _) => Some(version),
+        (None, Some(c)) => Some(c.resolve_current_version(channel.as_deref())?),
+        (None, None) => None,
+    };
 
     if version_str.is_none() {
-        return Err("No version provided and no config found".to_string());
+        return Err(VersionItError::ConfigNotFound("No version provided and no config found".to_string()));
     }
 
     let version_str = version_str.unwrap();
 
-    let scheme = scheme_override.or_else(|| config.as_ref().map(|c| c.versioning_scheme.clone())).unwrap_or("semantic".to_string());
-    let channel = channel_override.or_else(|| config.as_ref().and_then(|c| c.channel.clone()));
-    VersionInfo::new(&version_str, &scheme, channel).map_err(|e| format!("Error parsing version: {}", e))
+    let scheme = match scheme_override {
+        Some(name) => name.parse::<Scheme>()?,
+        None => config.map(|c| c.versioning_scheme).unwrap_or_default(),
+    };
+    log::debug!("Current version {} ({} scheme)", version_str, scheme);
+    let calver_format = config.map(|c| c.calver_format).unwrap_or_default();
+    let version_pattern = config.and_then(|c| c.version_pattern.as_deref()).filter(|_| scheme == Scheme::Pattern);
+    let mut v = match version_pattern {
+        Some(template) => VersionInfo::new_with_pattern(&version_str, template, channel),
+        None => VersionInfo::new_with_calver_format(&version_str, scheme, calver_format, channel),
+    }.map_err(|e| e.context("Error parsing version"))?;
+    if let Some(separator) = config.and_then(|c| c.channel_separator.as_deref()) {
+        v.set_channel_separator(separator);
+    }
+    if let Some(cfg) = config.filter(|c| c.calver_enable_branch && scheme == Scheme::Calver) {
+        let branch = cfg.get_current_branch().map_err(|e| e.context("Error reading current branch"))?;
+        let slug = slugify(&branch);
+        if !slug.is_empty() {
+            v.set_branch(&slug);
+        }
+    }
+    Ok(v)
+}
+
+pub fn apply_monotonic_step(v: &mut VersionInfo, bump_by: Option<u64>, config: Option<&Config>) {
+    if let Some(step) = bump_by.or_else(|| config.and_then(|c| c.monotonic_step)) {
+        v.set_monotonic_step(step);
+    }
 }
 
-pub fn apply_bump(v: &mut VersionInfo, bump: &str) -> Result<(), String> {
+pub fn apply_bump(v: &mut VersionInfo, bump: &str) -> Result<(), VersionItError> {
+    // Short numeric versions may lack the component, which bump_index reports
+    if let VersionType::Numeric(_) = v.version {
+        if let Some(index) = ["major", "minor", "patch"].iter().position(|b| *b == bump) {
+            return v.bump_index(index).map_err(|e| e.context("Error bumping version"));
+        }
+    }
     match bump {
         "major" => {
             v.bump_major();
@@ -54,208 +258,635 @@ pub fn apply_bump(v: &mut VersionInfo, bump: &str) -> Result<(), String> {
             v.bump_patch();
             Ok(())
         }
-        _ => Err(format!("Invalid bump type: {}. Use major, minor, or patch.", bump)),
+        "" => Err(VersionItError::InvalidArgument("No bump type given. Pass --bump major, minor or patch.".to_string())),
+        _ => Err(VersionItError::InvalidArgument(format!("Invalid bump type: {}. Use major, minor, patch or auto.", bump))),
+    }
+}
+
+pub fn apply_component_overrides(v: &mut VersionInfo, set_major: Option<u64>, set_minor: Option<u64>, set_patch: Option<u64>) -> Result<(), VersionItError> {
+    if let Some(major) = set_major {
+        v.set_major(major).map_err(|e| e.context("Error setting major"))?;
+    }
+    if let Some(minor) = set_minor {
+        v.set_minor(minor).map_err(|e| e.context("Error setting minor"))?;
+    }
+    if let Some(patch) = set_patch {
+        v.set_patch(patch).map_err(|e| e.context("Error setting patch"))?;
     }
+    Ok(())
 }
 
-pub fn handle_bump_command(options: BumpOptions, context: &CommandContext) {
-    let mut v = match get_version_info_with_scheme(options.version, &context.config, options.scheme, options.channel) {
-        Ok(v) => v,
-        Err(e) => output_error(context.structured_output, &e),
+/// Sets semver prerelease and build metadata after bumping.
+///
+/// Only semantic versions have these; a prerelease also conflicts with any channel
+/// other than stable, since channels add their own prerelease suffix.
+pub fn apply_prerelease_and_build(v: &mut VersionInfo, pre: Option<&str>, build: Option<&str>) -> Result<(), VersionItError> {
+    if pre.is_none() && build.is_none() {
+        return Ok(());
+    }
+    if !matches!(v.version, VersionType::Semantic(_)) {
+        return Err(VersionItError::InvalidArgument(format!("--pre and --build require the semantic scheme, not {}", v.scheme)));
+    }
+    if let Some(pre) = pre {
+        if let Some(channel) = v.channel.as_deref().filter(|c| *c != "stable") {
+            return Err(VersionItError::InvalidArgument(format!("--pre cannot be combined with channel '{}'; use --channel stable", channel)));
+        }
+        v.set_prerelease(pre);
+    }
+    if let Some(build) = build {
+        v.set_build(build);
+    }
+    if let VersionType::Semantic(ref semver) = v.version {
+        if pre.is_some_and(|p| semver.pre.as_str() != p) {
+            return Err(VersionItError::VersionParse(format!("Invalid prerelease identifier: {}", pre.unwrap_or_default())));
+        }
+        if build.is_some_and(|b| semver.build.as_str() != b) {
+            return Err(VersionItError::VersionParse(format!("Invalid build metadata: {}", build.unwrap_or_default())));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the snapshot suffix as a prerelease identifier, e.g. `SNAPSHOT`.
+fn snapshot_prerelease(config: Option<&Config>) -> String {
+    let suffix = config.map_or(version_it_core::config::DEFAULT_SNAPSHOT_SUFFIX, |c| c.snapshot_suffix());
+    suffix.trim_start_matches('-').to_string()
+}
+
+/// Drops the snapshot suffix from the current version for `--snapshot` and `--release`,
+/// so it is never appended twice.
+///
+/// # Returns
+///
+/// Whether the version was a snapshot.
+fn strip_snapshot(v: &mut VersionInfo, config: Option<&Config>, options: &BumpOptions) -> bool {
+    if !options.snapshot && !options.release {
+        return false;
+    }
+    let snapshot = snapshot_prerelease(config);
+    let is_snapshot = matches!(v.version, VersionType::Semantic(ref semver) if semver.pre.as_str() == snapshot);
+    if is_snapshot {
+        v.set_prerelease("");
+    }
+    is_snapshot
+}
+
+/// The prerelease to set after bumping: the snapshot suffix for `--snapshot`, else `--pre`.
+fn prerelease(config: Option<&Config>, options: &BumpOptions) -> Option<String> {
+    if options.snapshot {
+        Some(snapshot_prerelease(config))
+    } else {
+        options.pre.clone()
+    }
+}
+
+/// Checks whether bumping is allowed on the current branch.
+///
+/// Returns the current branch name if it is not listed in `run-on-branches`
+/// and branch enforcement is enabled, None otherwise.
+pub fn disallowed_branch(respect_branch: bool, config: Option<&Config>) -> Result<Option<String>, VersionItError> {
+    let enforce = respect_branch || config.map(|c| c.enforce_branches).unwrap_or(false);
+    if !enforce {
+        return Ok(None);
+    }
+    let cfg = config.ok_or_else(|| VersionItError::ConfigNotFound("--respect-branch requires a config with run-on-branches".to_string()))?;
+    let branch = cfg.get_current_branch().map_err(|e| e.context("Error reading current branch"))?;
+    if cfg.run_on_branches.contains(&branch) {
+        Ok(None)
+    } else {
+        Ok(Some(branch))
+    }
+}
+
+/// Reads a version from a plain version file such as `VERSION`.
+fn read_version_file(path: &Path) -> Result<String, VersionItError> {
+    std::fs::read_to_string(path)
+        .map(|content| content.trim().to_string())
+        .map_err(|e| VersionItError::from(e).context(&format!("Error reading version file '{}'", path.display())))
+}
+
+/// Renders the annotation for the release tag, or None for a lightweight tag.
+///
+/// Custom templates can use `{{changelog}}`, the release's changelog entry rendered
+/// like the changelog exporter would.
+fn tag_message(v: &VersionInfo, previous_version: &str, config: Option<&Config>, options: &BumpOptions) -> Result<Option<String>, VersionItError> {
+    let new_version = v.to_string();
+    if options.lightweight {
+        return Ok(None);
+    }
+    let template = match options.tag_message {
+        Some(ref template) => template,
+        None => return Ok(Some(format!("Version {}", new_version))),
     };
-    let old_version = v.to_string();
-    if let Err(e) = apply_bump(&mut v, &options.bump) {
-        output_error(context.structured_output, &e);
+    let changelog = match config {
+        Some(cfg) => {
+            let changelog = cfg.build_changelog(&new_version).map_err(|e| e.context("Error building changelog"))?;
+            cfg.render_changelog(&changelog).map_err(|e| e.context("Error rendering changelog"))?
+        }
+        None => String::new(),
+    };
+    render_tag_message(template, &new_version, previous_version, v.scheme.as_str(), v.channel.as_deref(), changelog.trim_end())
+        .map(Some)
+        .map_err(|e| e.context("Error rendering tag message"))
+}
+
+/// Writes `<file>.sha256` for a file written by the bump when `emit-checksum` is set.
+///
+/// `name` is the file as configured, which is what the recorded operation shows.
+fn emit_checksum(path: &Path, name: &str, config: Option<&Config>, dry_run: bool, result: &mut BumpResult) -> Result<(), VersionItError> {
+    if !config.is_some_and(|c| c.emit_checksum) {
+        return Ok(());
+    }
+    if !dry_run {
+        write_checksum(path).map_err(|e| VersionItError::from(e).context(&format!("Error writing checksum of '{}'", name)))?;
     }
+    let checksum = checksum_path(name).display().to_string();
+    result.operations.push(format!("Write checksum '{}'", checksum));
+    result.plan.checksums.push(checksum);
+    Ok(())
+}
 
+/// Writes the version file, headers, package files and changelog, then commits and tags.
+///
+/// Under dry run nothing is touched and only the planned operations are recorded.
+fn write_version(v: &VersionInfo, previous_version: String, config: Option<&Config>, options: &BumpOptions) -> Result<BumpResult, VersionItError> {
+    let BumpOptions { create_tag, commit, no_verify, amend, dry_run, .. } = *options;
+    let bump_type = options.bump.clone();
     let new_version = v.to_string();
-    if context.structured_output {
-        let data = serde_json::json!({
-            "success": true,
-            "version": new_version,
-            "previous_version": old_version,
-            "bump_type": options.bump
-        });
-        output_success(context.structured_output, data);
+    if commit && amend && !options.force {
+        let pushed = git_remote_branches_containing_head().map_err(|e| e.context("Error checking whether HEAD was pushed"))?;
+        if !pushed.is_empty() {
+            return Err(VersionItError::Git(format!("Refusing to amend a commit already pushed to {}; pass --force to amend it anyway", pushed.join(", "))));
+        }
+    }
+    // Only the tag on the commit being amended moves along with it, unless forced
+    let move_tag = if commit && amend && create_tag {
+        match git_commit_of(&format!("refs/tags/{}", new_version))? {
+            Some(tagged) if !options.force && git_commit_of("HEAD")?.as_ref() != Some(&tagged) => {
+                return Err(VersionItError::Git(format!("Tag '{}' already exists on another commit; pass --force to move it", new_version)));
+            }
+            tagged => tagged.is_some(),
+        }
     } else {
-        println!("{}", new_version);
+        false
+    };
+    // Rendered before committing, so the changelog only covers the released commits
+    let message = if create_tag { tag_message(v, &previous_version, config, options)? } else { None };
+    let mut result = BumpResult {
+        previous_version,
+        version: new_version.clone(),
+        scheme: v.scheme.to_string(),
+        channel: v.channel.clone(),
+        bump_type,
+        dry_run,
+        operations: Vec::new(),
+        plan: BumpPlan::default(),
+        committed: false,
+        tagged: false,
+        nothing_to_tag: false,
+        divergence: None,
+    };
+
+    if let Some(ref path) = options.version_file {
+        if !dry_run {
+            write_atomic(path, &new_version)
+                .map_err(|e| VersionItError::from(e).context("Error writing version to file"))?;
+        }
+        result.operations.push(format!("Write version '{}' to file '{}'", new_version, path.display()));
+        result.plan.version_files.push(path.display().to_string());
+        emit_checksum(path, &path.display().to_string(), config, dry_run, &mut result)?;
+    }
+
+    for path in &options.write_to {
+        if !dry_run {
+            write_atomic(path, &new_version)
+                .map_err(|e| VersionItError::from(e).context(&format!("Error writing version to '{}'", path.display())))?;
+        }
+        result.operations.push(format!("Write version '{}' to file '{}'", new_version, path.display()));
+        result.plan.version_files.push(path.display().to_string());
+        emit_checksum(path, &path.display().to_string(), config, dry_run, &mut result)?;
     }
 
-    if options.dry_run {
-        println!("DRY RUN: Would perform the following operations:");
-        if let Some(ref cfg) = &context.config {
-            if let Some(ref file) = cfg.current_version_file {
-                println!("  - Write version '{}' to file '{}'", new_version, file);
+    if let Some(cfg) = config.filter(|_| options.version_file.is_none()) {
+        if let Some(source) = cfg.version_source.package_file() {
+            if !dry_run {
+                cfg.write_source_version(&source, &new_version)
+                    .map_err(|e| e.context(&format!("Error writing version to '{}'", source.path)))?;
             }
-            if let Some(ref headers) = cfg.version_headers {
-                for header in headers {
-                    println!("  - Generate header file '{}'", header.path);
-                }
+            result.operations.push(format!("Write version '{}' to file '{}'", new_version, source.path));
+            result.plan.version_files.push(source.path.clone());
+            emit_checksum(&cfg.resolve_path(&source.path), &source.path, config, dry_run, &mut result)?;
+        }
+    }
+
+    if let Some(cfg) = config {
+        if let Some(file) = cfg.version_file_for(v.channel.as_deref()).filter(|_| options.version_file.is_none() && cfg.version_source == VersionSource::File) {
+            if !dry_run {
+                write_atomic(cfg.resolve_path(file), &new_version)
+                    .map_err(|e| VersionItError::from(e).context("Error writing version to file"))?;
             }
-            if let Some(ref package_files) = cfg.package_files {
-                for package_file in package_files {
-                    println!("  - Update version in '{}' ({})", package_file.path, package_file.manager);
+            result.operations.push(format!("Write version '{}' to file '{}'", new_version, file));
+            result.plan.version_files.push(file.to_string());
+            emit_checksum(&cfg.resolve_path(file), file, config, dry_run, &mut result)?;
+        }
+        if !dry_run {
+            cfg.generate_headers(&new_version, v.channel.as_deref())
+                .map_err(|e| e.context("Error generating headers"))?;
+        }
+        if let Some(ref headers) = cfg.version_headers {
+            for header in headers {
+                result.operations.push(format!("Generate header file '{}'", header.path));
+                result.plan.headers.push(header.path.clone());
+                if cfg.emit_checksum {
+                    // Written by generate_headers; only recorded here
+                    let checksum = checksum_path(&header.path).display().to_string();
+                    result.operations.push(format!("Write checksum '{}'", checksum));
+                    result.plan.checksums.push(checksum);
                 }
             }
         }
-        if options.commit {
-            println!("  - Commit changes with message 'Bump version to {}'", new_version);
-        }
-        if options.create_tag {
-            println!("  - Create git tag '{}'", new_version);
+        if !dry_run {
+            cfg.update_package_files(&new_version)
+                .map_err(|e| e.context("Error updating package files"))?;
         }
-    } else {
-        if let Some(ref cfg) = &context.config {
-            if let Some(ref file) = cfg.current_version_file {
-                if let Err(e) = std::fs::write(file, &new_version) {
-                    output_error(context.structured_output, &format!("Error writing version to file: {}", e));
+        if let Some(ref package_files) = cfg.package_files {
+            for package_file in package_files {
+                match package_file.dependency {
+                    Some(ref dependency) => result.operations.push(format!("Update dependency '{}' in '{}' ({})", dependency, package_file.path, package_file.manager)),
+                    None => result.operations.push(format!("Update version in '{}' ({})", package_file.path, package_file.manager)),
+                }
+                result.plan.package_files.push(package_file.path.clone());
+                if package_file.manager == "npm" && package_file.dependency.is_none() && package_file.field.as_deref().unwrap_or("version") == "version" {
+                    if let Some(lockfile) = npm_lockfile(&cfg.resolve_path(&package_file.path)) {
+                        result.operations.push(format!("Update version in '{}' (npm)", lockfile.display()));
+                        result.plan.package_files.push(lockfile.display().to_string());
+                    }
                 }
             }
-            if let Err(e) = cfg.generate_headers(&new_version, v.channel.as_deref()) {
-                output_error(context.structured_output, &format!("Error generating headers: {}", e));
-            }
-            if let Err(e) = cfg.update_package_files(&new_version) {
-                output_error(context.structured_output, &format!("Error updating package files: {}", e));
-            }
         }
+        if !dry_run {
+            cfg.generate_changelog(&new_version)
+                .map_err(|e| e.context("Error generating changelog"))?;
+        }
+        for exporter in &cfg.changelog_exporters {
+            result.operations.push(format!("Update changelog '{}'", exporter.output_path));
+            result.plan.changelogs.push(exporter.output_path.clone());
+        }
+    }
+
+    // Git operations
+    let retries = config.and_then(|c| c.git_retries).unwrap_or(DEFAULT_GIT_RETRIES);
+    if commit {
+        if !dry_run {
+            result.committed = git_commit_changes(&new_version, no_verify, amend, retries).map_err(|e| e.context("Error committing changes"))?;
+        }
+        if amend {
+            result.operations.push("Amend the last commit".to_string());
+        } else {
+            result.operations.push(format!("Commit changes with message 'Bump version to {}'", new_version));
+        }
+        result.plan.commit = true;
+        result.plan.amend = amend;
+    }
+
+    // A re-run that changed nothing must not tag the unchanged tree
+    if create_tag && commit && !dry_run && !result.committed {
+        result.nothing_to_tag = true;
+    } else if create_tag {
+        if !dry_run {
+            git_create_tag(&new_version, message.as_deref(), move_tag, retries).map_err(|e| e.context("Error creating tag"))?;
+            result.tagged = true;
+        }
+        result.plan.tag = Some(new_version.clone());
+        result.plan.lightweight_tag = options.lightweight;
+        if move_tag {
+            result.operations.push(format!("Move git tag '{}' to the amended commit", new_version));
+        } else if options.lightweight {
+            result.operations.push(format!("Create lightweight git tag '{}'", new_version));
+        } else {
+            result.operations.push(format!("Create git tag '{}'", new_version));
+        }
+    }
+
+    Ok(result)
+}
 
-        // Git operations
-        if options.commit {
-            if let Err(e) = git_commit_changes(&new_version) {
-                output_error(context.structured_output, &format!("Error committing changes: {}", e));
+/// Bumps the version and writes it out according to the config.
+///
+/// # Arguments
+///
+/// * `options` - The bump type, overrides and git/dry-run flags.
+/// * `config` - The loaded config, if any.
+///
+/// # Returns
+///
+/// The outcome of the bump, or an error.
+pub fn run_bump(mut options: BumpOptions, config: Option<&Config>) -> Result<BumpOutcome, VersionItError> {
+    if let Some(branch) = disallowed_branch(options.respect_branch, config)? {
+        return Ok(BumpOutcome::Skipped { branch });
+    }
+    // Held until the bump is written, committed and tagged; dry runs change nothing
+    let _lock = match &options.lock {
+        Some(path) if !options.dry_run => Some(acquire_lock(path, options.lock_timeout.unwrap_or(DEFAULT_LOCK_TIMEOUT_SECS))?),
+        _ => None,
+    };
+    let mut divergence = None;
+    if options.bump == "auto" {
+        divergence = check_version_tag_divergence(config, options.channel.as_deref(), options.require_tag_match)?;
+        match resolve_auto_bump(config, options.since.as_deref(), options.max_bump.as_deref(), options.first_parent)? {
+            Some(bump_type) => {
+                log::debug!("Commits since the last version tag call for a {} bump", bump_type);
+                options.bump = bump_type;
             }
+            None => return Ok(BumpOutcome::NoBumpNeeded),
         }
+    }
+    let version = match (options.version.clone(), &options.version_file) {
+        (None, Some(path)) => Some(read_version_file(path)?),
+        (version, _) => version,
+    };
+    let mut v = get_version_info_with_scheme(version, config, options.scheme.clone(), options.channel.clone())?;
+    apply_monotonic_step(&mut v, options.bump_by, config);
+    let previous = v.clone();
+    let previous_version = v.to_string();
+    let releasing_snapshot = strip_snapshot(&mut v, config, &options) && options.release;
+    match options.bump_index {
+        Some(index) => v.bump_index(index).map_err(|e| e.context("Error bumping version"))?,
+        None if releasing_snapshot => {}
+        None => apply_bump(&mut v, &options.bump)?,
+    }
+    apply_component_overrides(&mut v, options.set_major, options.set_minor, options.set_patch)?;
+    apply_prerelease_and_build(&mut v, prerelease(config, &options).as_deref(), options.build.as_deref())?;
+    if !options.allow_downgrade {
+        check_downgrade(&v, &previous, config, &options)?;
+    }
+
+    let mut result = write_version(&v, previous_version, config, &options)?;
+    result.divergence = divergence;
+    Ok(BumpOutcome::Bumped(Box::new(result)))
+}
 
-        if options.create_tag {
-            if let Err(e) = git_create_tag(&new_version) {
-                output_error(context.structured_output, &format!("Error creating tag: {}", e));
+/// Seconds to wait for `--lock` when no `--lock-timeout` is given.
+pub const DEFAULT_LOCK_TIMEOUT_SECS: u64 = 60;
+
+/// Takes an exclusive advisory lock on a file, creating it if needed, and waits up to
+/// `timeout_secs` while another run holds it. The lock is released when the returned
+/// file is dropped.
+fn acquire_lock(path: &Path, timeout_secs: u64) -> Result<std::fs::File, VersionItError> {
+    use fs2::FileExt;
+    let file = std::fs::OpenOptions::new().create(true).truncate(false).write(true).open(path)
+        .map_err(|e| VersionItError::from(e).context(&format!("Error opening lock file {}", path.display())))?;
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(timeout_secs);
+    loop {
+        match file.try_lock_exclusive() {
+            Ok(()) => return Ok(file),
+            Err(e) if e.raw_os_error() != fs2::lock_contended_error().raw_os_error() => {
+                return Err(VersionItError::from(e).context(&format!("Error locking {}", path.display())));
+            }
+            Err(_) if std::time::Instant::now() >= deadline => {
+                let message = format!("Timed out after {}s waiting for the lock on {}; another version-it run holds it", timeout_secs, path.display());
+                return Err(VersionItError::Io(std::io::Error::new(std::io::ErrorKind::TimedOut, message)));
+            }
+            Err(_) => {
+                log::debug!("Waiting for the lock on {}", path.display());
+                std::thread::sleep(std::time::Duration::from_millis(100));
             }
         }
     }
 }
 
-pub fn handle_next_command(options: BumpOptions, context: &CommandContext) {
-    let mut v = match get_version_info_with_scheme(options.version, &context.config, options.scheme, options.channel) {
-        Ok(v) => v,
-        Err(e) => output_error(context.structured_output, &e),
+/// Refuses a new version lower than the version it was bumped from, or lower than the
+/// stored current version when an explicit `--version` was passed, which may be stale.
+fn check_downgrade(v: &VersionInfo, previous: &VersionInfo, config: Option<&Config>, options: &BumpOptions) -> Result<(), VersionItError> {
+    let mut current = vec![previous.clone()];
+    if options.version.is_some() && (config.is_some() || options.version_file.is_some()) {
+        let stored = options.version_file.as_deref().map(read_version_file).transpose()?;
+        if let Ok(stored) = get_version_info_with_scheme(stored, config, options.scheme.clone(), options.channel.clone()) {
+            current.push(stored);
+        }
+    }
+    match current.iter().find(|c| v.compare(c) == Some(Ordering::Less)) {
+        Some(current) => Err(VersionItError::InvalidArgument(format!("Refusing to downgrade from {} to {}; pass --allow-downgrade to write it anyway", current, v))),
+        None => Ok(()),
+    }
+}
+
+/// Checks that the version file agrees with the latest version tag before an auto bump.
+///
+/// Returns the differing versions so they can be reported, or an error when `strict`.
+fn check_version_tag_divergence(config: Option<&Config>, channel_override: Option<&str>, strict: bool) -> Result<Option<(String, String)>, VersionItError> {
+    let cfg = match config {
+        Some(cfg) => cfg,
+        None => return Ok(None),
     };
-    if let Err(e) = apply_bump(&mut v, &options.bump) {
-        output_error(context.structured_output, &e);
+    let channel = channel_override.or(cfg.channel.as_deref());
+    let divergence = cfg.version_tag_divergence(channel).map_err(|e| e.context("Error reading latest version tag"))?;
+    match divergence {
+        Some((file_version, tag)) if strict => Err(VersionItError::Git(format!("Version file says {} but the latest version tag is {}", file_version, tag))),
+        divergence => Ok(divergence),
     }
+}
 
-    let next_version = v.to_string();
-    if context.structured_output {
-        let data = serde_json::json!({
-            "success": true,
-            "version": next_version
-        });
-        output_success(context.structured_output, data);
+/// Determines the bump type for `--bump auto` from the commits since the last version tag,
+/// or since `since` when given, capped at `max_bump` (or `max-auto-bump`). With
+/// `first_parent`, only mainline commits are analyzed.
+fn resolve_auto_bump(config: Option<&Config>, since: Option<&str>, max_bump: Option<&str>, first_parent: bool) -> Result<Option<String>, VersionItError> {
+    let cfg = config.ok_or_else(|| VersionItError::ConfigNotFound("--bump auto requires a config with change-type-map".to_string()))?;
+    let overridden;
+    let cfg = if max_bump.is_some() || first_parent {
+        overridden = Config {
+            max_auto_bump: max_bump.map(str::to_string).or_else(|| cfg.max_auto_bump.clone()),
+            commit_analysis_first_parent: first_parent || cfg.commit_analysis_first_parent,
+            ..cfg.clone()
+        };
+        overridden.validate()?;
+        &overridden
     } else {
-        println!("{}", next_version);
-    }
-}
-
-pub fn handle_auto_bump_command(options: AutoBumpOptions, context: &CommandContext) {
-    if let Some(ref cfg) = &context.config {
-        match cfg.analyze_commits_for_bump() {
-            Ok(Some(bump_type)) => {
-                // Get current version from file or latest tag or config
-                let current_version = cfg.get_current_version().unwrap_or_else(|_| {
-                    cfg.get_latest_version_tag().unwrap_or(Some(cfg.first_version.clone())).unwrap_or(cfg.first_version.clone())
-                });
-                let v_result = VersionInfo::new(&current_version, &cfg.versioning_scheme, cfg.channel.clone());
-
-                        if let Err(e) = &v_result {
-                            output_error(context.structured_output, &format!("Error parsing version: {}", e));
-                        }
-
-                let mut v = v_result.unwrap();
-
-                        match bump_type.as_str() {
-                            "major" => v.bump_major(),
-                            "minor" => v.bump_minor(),
-                            "patch" => v.bump_patch(),
-                            _ => {
-                                output_error(context.structured_output, &format!("Unknown bump type: {}", bump_type));
-                            }
-                        }
-
-                        let new_version = v.to_string();
-                        if context.structured_output {
-                            let data = serde_json::json!({
-                                "success": true,
-                                "version": new_version,
-                                "bump_type": bump_type
-                            });
-                            output_success(context.structured_output, data);
-                        } else {
-                            println!("{}", new_version);
-                        }
-
-                        if options.dry_run {
-                            println!("DRY RUN: Would perform the following operations:");
-                            if let Some(ref file) = cfg.current_version_file {
-                                println!("  - Write version '{}' to file '{}'", new_version, file);
-                            }
-                            if let Some(ref headers) = cfg.version_headers {
-                                for header in headers {
-                                    println!("  - Generate header file '{}'", header.path);
-                                }
-                            }
-                            if let Some(ref package_files) = cfg.package_files {
-                                for package_file in package_files {
-                                    println!("  - Update version in '{}' ({})", package_file.path, package_file.manager);
-                                }
-                            }
-                            if options.commit {
-                                println!("  - Commit changes with message 'Bump version to {}'", new_version);
-                            }
-                            if options.create_tag {
-                                println!("  - Create git tag '{}'", new_version);
-                            }
-                        } else {
-                            if let Some(ref file) = cfg.current_version_file {
-                                if let Err(e) = std::fs::write(file, &new_version) {
-                                    output_error(context.structured_output, &format!("Error writing version to file: {}", e));
-                                }
-                            }
-                            if let Err(e) = cfg.generate_headers(&new_version, v.channel.as_deref()) {
-                                output_error(context.structured_output, &format!("Error generating headers: {}", e));
-                            }
-                            if let Err(e) = cfg.update_package_files(&new_version) {
-                                output_error(context.structured_output, &format!("Error updating package files: {}", e));
-                            }
-
-                            // Git operations
-                            if options.commit {
-                                if let Err(e) = git_commit_changes(&new_version) {
-                                    output_error(context.structured_output, &format!("Error committing changes: {}", e));
-                                }
-                            }
-
-                            if options.create_tag {
-                                if let Err(e) = git_create_tag(&new_version) {
-                                    output_error(context.structured_output, &format!("Error creating tag: {}", e));
-                                }
-                            }
-                }
-            }
-                    Ok(None) => {
-                        if context.structured_output {
-                            let data = serde_json::json!({
-                                "success": true,
-                                "message": "No bump needed"
-                            });
-                            output_success(context.structured_output, data);
-                        } else {
-                            println!("No bump needed");
-                        }
-                    }
-                    Err(e) => {
-                        output_error(context.structured_output, &format!("Error analyzing commits: {}", e));
-                    }
-                }
-            } else {
-                output_error(context.structured_output, "No config found for auto-bump");
+        cfg
+    };
+    cfg.analyze_commits_for_bump_since(since).map_err(|e| e.context("Error analyzing commits"))
+}
+
+/// Computes the next version, writing it only to the `write_to` files.
+///
+/// With `--bump auto` and no commit warranting a bump, the next version is the current one.
+pub fn run_next(options: BumpOptions, config: Option<&Config>) -> Result<NextOutcome, VersionItError> {
+    let version = match (options.version.clone(), &options.version_file) {
+        (None, Some(path)) => Some(read_version_file(path)?),
+        (version, _) => version,
+    };
+    let mut v = get_version_info_with_scheme(version, config, options.scheme.clone(), options.channel.clone())?;
+    apply_monotonic_step(&mut v, options.bump_by, config);
+    let previous_version = v.to_string();
+    let releasing_snapshot = strip_snapshot(&mut v, config, &options) && options.release;
+    if let Some(index) = options.bump_index {
+        v.bump_index(index).map_err(|e| e.context("Error bumping version"))?;
+    } else if releasing_snapshot {
+        // The release of a snapshot is the snapshot without its suffix
+    } else if options.bump == "auto" {
+        if let Some(bump_type) = resolve_auto_bump(config, options.since.as_deref(), options.max_bump.as_deref(), options.first_parent)? {
+            apply_bump(&mut v, &bump_type)?;
+        }
+    } else {
+        apply_bump(&mut v, &options.bump)?;
+    }
+    apply_component_overrides(&mut v, options.set_major, options.set_minor, options.set_patch)?;
+    apply_prerelease_and_build(&mut v, prerelease(config, &options).as_deref(), options.build.as_deref())?;
+    for path in &options.write_to {
+        write_atomic(path, v.to_string())
+            .map_err(|e| VersionItError::from(e).context(&format!("Error writing version to '{}'", path.display())))?;
+    }
+
+    Ok(NextOutcome {
+        previous_version,
+        version: v.to_string(),
+        scheme: v.scheme.to_string(),
+        channel: v.channel.clone(),
+    })
+}
+
+/// How a generated header changes from the current to the next version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderDiff {
+    /// The header path from `version-headers`.
+    pub path: String,
+    /// Unified diff of the rendered header; empty when the content is the same.
+    pub diff: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffOutcome {
+    pub previous_version: String,
+    pub version: String,
+    /// One entry per configured header, in config order.
+    pub headers: Vec<HeaderDiff>,
+}
+
+/// Renders the version headers at the current and the next version, without writing
+/// anything, and diffs them.
+///
+/// # Returns
+///
+/// The diff of every header, or an error when no `version-headers` are configured.
+pub fn run_diff(options: BumpOptions, config: Option<&Config>) -> Result<DiffOutcome, VersionItError> {
+    let cfg = config
+        .filter(|c| c.version_headers.as_ref().is_some_and(|h| !h.is_empty()))
+        .ok_or_else(|| VersionItError::ConfigNotFound("diff requires a config with version-headers".to_string()))?;
+    let next = run_next(options, config)?;
+    let render = |version: &str| {
+        cfg.render_headers(version, next.channel.as_deref()).map_err(|e| e.context("Error rendering headers"))
+    };
+    let headers = render(&next.previous_version)?
+        .into_iter()
+        .zip(render(&next.version)?)
+        .map(|((path, old), (_, new))| {
+            let diff = similar::TextDiff::from_lines(&old, &new)
+                .unified_diff()
+                .header(&format!("{} ({})", path, next.previous_version), &format!("{} ({})", path, next.version))
+                .to_string();
+            HeaderDiff { path, diff }
+        })
+        .collect();
+    Ok(DiffOutcome {
+        previous_version: next.previous_version,
+        version: next.version,
+        headers,
+    })
+}
+
+/// Determines the bump the commits since the last version tag call for, without
+/// reading or writing any version, header or git state.
+///
+/// # Returns
+///
+/// The bump type (`major`, `minor` or `patch`), None when no bump is needed, or an error.
+pub fn check_auto_bump(options: &AutoBumpOptions, config: &Config) -> Result<Option<String>, VersionItError> {
+    resolve_auto_bump(Some(config), options.since.as_deref(), options.max_bump.as_deref(), options.first_parent)
+}
+
+/// Bumps the version based on the commits since the last version tag.
+///
+/// This is `run_bump` with `--bump auto`.
+pub fn run_auto_bump(options: AutoBumpOptions, config: &Config) -> Result<AutoBumpOutcome, VersionItError> {
+    let bump_options = BumpOptions {
+        bump: "auto".to_string(),
+        create_tag: options.create_tag,
+        tag_message: options.tag_message,
+        lightweight: options.lightweight,
+        commit: options.commit,
+        no_verify: options.no_verify,
+        amend: options.amend,
+        force: options.force,
+        dry_run: options.dry_run,
+        require_tag_match: options.require_tag_match,
+        since: options.since,
+        max_bump: options.max_bump,
+        first_parent: options.first_parent,
+        lock: options.lock,
+        lock_timeout: options.lock_timeout,
+        ..Default::default()
+    };
+    match run_bump(bump_options, Some(config))? {
+        BumpOutcome::Bumped(result) => Ok(AutoBumpOutcome::Bumped(result)),
+        BumpOutcome::Skipped { .. } | BumpOutcome::NoBumpNeeded => Ok(AutoBumpOutcome::NoBumpNeeded),
+    }
+}
+
+/// Finds the version in a tag such as `v1.2.3` or `release-1.2.4`, skipping any prefix
+/// before the first digit.
+///
+/// A bare number is only a version with `monotonic`, since otherwise any tag containing
+/// a number would be one, e.g. `jdk17`.
+fn tag_version(tag: &str, monotonic: bool) -> Option<VersionInfo> {
+    let start = tag.find(|c: char| c.is_ascii_digit())?;
+    VersionInfo::detect(&tag[start..]).filter(|v| monotonic || v.scheme != Scheme::Monotonic)
+}
+
+/// Re-creates version tags under a normalized name.
+///
+/// Tags whose version can't be detected are left alone, as are tags that already have
+/// their normalized name. Numbered tags such as `build-42` only count as versions when
+/// the configured scheme is monotonic. Nothing is touched unless `options.apply` is set.
+///
+/// # Arguments
+///
+/// * `options` - The name format and whether to apply the renames and delete old tags.
+/// * `config` - The loaded config, if any, for `git-retries` and `versioning-scheme`.
+///
+/// # Returns
+///
+/// The planned or applied renames, or an error.
+pub fn run_retag(options: RetagOptions, config: Option<&Config>) -> Result<RetagOutcome, VersionItError> {
+    let format = options.format.as_deref().unwrap_or("{{version}}");
+    let retries = config.and_then(|c| c.git_retries).unwrap_or(DEFAULT_GIT_RETRIES);
+    let monotonic = config.is_some_and(|c| c.versioning_scheme == Scheme::Monotonic);
+    let tags = git_list_tags().map_err(|e| e.context("Error listing tags"))?;
+    let mut outcome = RetagOutcome { applied: options.apply, ..Default::default() };
+
+    for tag in &tags {
+        let version = match tag_version(tag, monotonic) {
+            Some(version) => version,
+            None => continue,
+        };
+        let version_str = version.to_string();
+        let to = render_version_format(format, &version_str, &version_str, version.scheme.as_str(), None)
+            .map_err(|e| e.context("Error rendering format"))?;
+        if &to == tag {
+            continue;
+        }
+        let rename = TagRename { from: tag.clone(), to };
+        // Two old tags can map to the same new name, so check against what was created too
+        if tags.contains(&rename.to) || outcome.renames.iter().any(|r| r.to == rename.to) {
+            outcome.conflicts.push(rename);
+            continue;
+        }
+        if options.apply {
+            git_copy_tag(&rename.from, &rename.to, retries).map_err(|e| e.context(&format!("Error creating tag '{}'", rename.to)))?;
+            if options.delete_old {
+                git_delete_tag(&rename.from, retries).map_err(|e| e.context(&format!("Error deleting tag '{}'", rename.from)))?;
             }
-}
\ No newline at end of file
+        }
+        outcome.renames.push(rename);
+    }
+    Ok(outcome)
+}
@@ -1,13 +1,64 @@
-use std::process::Command;
+use std::process::{Command, Output};
+use std::time::Duration;
+use version_it_core::VersionItError;
 
-pub fn git_commit_changes(version: &str) -> Result<(), Box<dyn std::error::Error>> {
+/// Retries for git commands failing on a lock when the config doesn't set `git-retries`.
+pub const DEFAULT_GIT_RETRIES: u32 = 2;
+
+/// Whether git failed because another git process holds a lock, e.g. `index.lock`.
+fn is_lock_error(stderr: &str) -> bool {
+    stderr.contains(".lock") && (stderr.contains("File exists") || stderr.contains("Another git process"))
+}
+
+/// Runs a git command, retrying with exponential backoff while it fails on a lock.
+///
+/// Other failures are returned right away, since running them again won't help.
+///
+/// # Arguments
+///
+/// * `args` - The git arguments.
+/// * `retries` - How often to retry after a lock failure.
+///
+/// # Returns
+///
+/// The output of the last attempt.
+pub fn run_git_with_retry(args: &[&str], retries: u32) -> std::io::Result<Output> {
+    let mut attempt = 0;
+    loop {
+        let output = Command::new("git").args(args).output()?;
+        if output.status.success() || attempt >= retries || !is_lock_error(&String::from_utf8_lossy(&output.stderr)) {
+            return Ok(output);
+        }
+        attempt += 1;
+        log::debug!("git {} hit a lock, retrying ({}/{})", args.join(" "), attempt, retries);
+        std::thread::sleep(Duration::from_millis(100 << attempt));
+    }
+}
+
+/// Builds an error from a failed git command, including what git reported.
+fn git_error(context: &str, output: &Output) -> VersionItError {
+    VersionItError::Git(format!("{}: {}", context, String::from_utf8_lossy(&output.stderr).trim()))
+}
+
+/// Stages and commits all changes for a version bump.
+///
+/// # Arguments
+///
+/// * `version` - The new version, used in the commit message.
+/// * `no_verify` - Pass `--no-verify` to skip the pre-commit and commit-msg hooks.
+/// * `amend` - Fold the changes into the last commit instead, keeping its message;
+///   this happens even when nothing changed.
+/// * `retries` - How often to retry git commands that fail on a lock.
+///
+/// # Returns
+///
+/// Whether a commit was created or amended; false when there was nothing to commit.
+pub fn git_commit_changes(version: &str, no_verify: bool, amend: bool, retries: u32) -> Result<bool, VersionItError> {
     // Add all changes to git
-    let status = Command::new("git")
-        .args(["add", "."])
-        .status()?;
+    let output = run_git_with_retry(&["add", "."], retries)?;
 
-    if !status.success() {
-        return Err("Failed to add files to git".into());
+    if !output.status.success() {
+        return Err(git_error("Failed to add files to git", &output));
     }
 
     // Check if there are any changes to commit
@@ -15,36 +66,113 @@ pub fn git_commit_changes(version: &str) -> Result<(), Box<dyn std::error::Error
         .args(["status", "--porcelain"])
         .output()?;
 
-    if status_output.stdout.is_empty() {
+    if status_output.stdout.is_empty() && !amend {
         // No changes to commit
-        return Ok(());
+        return Ok(false);
     }
 
     // Commit the changes
     let commit_message = format!("Bump version to {}", version);
-    let status = Command::new("git")
-        .args(["commit", "-m", &commit_message])
-        .status()?;
+    let mut args = if amend { vec!["commit", "--amend", "--no-edit"] } else { vec!["commit", "-m", &commit_message] };
+    if no_verify {
+        args.push("--no-verify");
+    }
+    let output = run_git_with_retry(&args, retries)?;
+
+    if !output.status.success() {
+        return Err(git_error(if amend { "Failed to amend the last commit" } else { "Failed to commit changes" }, &output));
+    }
+
+    Ok(true)
+}
+
+/// Creates a tag for the version.
+///
+/// # Arguments
+///
+/// * `version` - The tag name.
+/// * `message` - The annotation; None creates a lightweight tag.
+/// * `force` - Move an existing tag of the same name, e.g. after amending its commit.
+/// * `retries` - How often to retry when git fails on a lock.
+pub fn git_create_tag(version: &str, message: Option<&str>, force: bool, retries: u32) -> Result<(), VersionItError> {
+    let mut args = vec!["tag"];
+    if force {
+        args.push("-f");
+    }
+    match message {
+        // Keep markdown headings, which git would strip as comment lines
+        Some(message) => args.extend(["-a", "--cleanup=whitespace", version, "-m", message]),
+        None => args.push(version),
+    }
+    let output = run_git_with_retry(&args, retries)?;
 
-    if !status.success() {
-        return Err("Failed to commit changes".into());
+    if !output.status.success() {
+        return Err(git_error("Failed to create git tag", &output));
     }
 
-    println!("Committed version bump: {}", version);
     Ok(())
 }
 
-pub fn git_create_tag(version: &str) -> Result<(), Box<dyn std::error::Error>> {
-    // Create an annotated tag
-    let tag_message = format!("Version {}", version);
-    let status = Command::new("git")
-        .args(["tag", "-a", version, "-m", &tag_message])
-        .status()?;
+/// Resolves a revision such as `HEAD` or `refs/tags/1.2.3` to its commit hash, or None
+/// when it doesn't exist.
+pub fn git_commit_of(rev: &str) -> Result<Option<String>, VersionItError> {
+    let output = Command::new("git").args(["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)]).output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+/// Lists the remote-tracking branches that already contain HEAD, i.e. where it was pushed.
+pub fn git_remote_branches_containing_head() -> Result<Vec<String>, VersionItError> {
+    let output = Command::new("git").args(["branch", "-r", "--contains", "HEAD", "--format=%(refname:short)"]).output()?;
+    if !output.status.success() {
+        return Err(git_error("Failed to list remote branches", &output));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).lines().map(|l| l.to_string()).collect())
+}
 
-    if !status.success() {
-        return Err("Failed to create git tag".into());
+/// Lists all tags of the repository.
+pub fn git_list_tags() -> Result<Vec<String>, VersionItError> {
+    let output = Command::new("git").args(["tag", "--list"]).output()?;
+    if !output.status.success() {
+        return Err(git_error("Failed to list git tags", &output));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).lines().map(|l| l.to_string()).collect())
+}
+
+/// Creates a tag with a new name on the commit of an existing tag.
+///
+/// Annotated tags keep their message; lightweight tags stay lightweight.
+///
+/// # Arguments
+///
+/// * `old` - The existing tag.
+/// * `new` - The name of the new tag.
+/// * `retries` - How often to retry when git fails on a lock.
+pub fn git_copy_tag(old: &str, new: &str, retries: u32) -> Result<(), VersionItError> {
+    let target = format!("refs/tags/{}^{{commit}}", old);
+    let object_type = Command::new("git").args(["cat-file", "-t", &format!("refs/tags/{}", old)]).output()?;
+    let output = if String::from_utf8_lossy(&object_type.stdout).trim() == "tag" {
+        let contents = Command::new("git").args(["for-each-ref", "--format=%(contents)", &format!("refs/tags/{}", old)]).output()?;
+        let message = String::from_utf8_lossy(&contents.stdout).into_owned();
+        run_git_with_retry(&["tag", "-a", "--cleanup=whitespace", new, &target, "-m", &message], retries)?
+    } else {
+        run_git_with_retry(&["tag", new, &target], retries)?
+    };
+
+    if !output.status.success() {
+        return Err(git_error("Failed to create git tag", &output));
     }
 
-    println!("Created git tag: {}", version);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Deletes a tag.
+pub fn git_delete_tag(tag: &str, retries: u32) -> Result<(), VersionItError> {
+    let output = run_git_with_retry(&["tag", "-d", tag], retries)?;
+    if !output.status.success() {
+        return Err(git_error("Failed to delete git tag", &output));
+    }
+    Ok(())
+}
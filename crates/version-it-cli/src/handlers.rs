@@ -0,0 +1,347 @@
+use version_it_cli::{run_bump, run_next, run_auto_bump, check_auto_bump, run_retag, run_diff, BumpOptions, AutoBumpOptions, BumpOutcome, BumpResult, AutoBumpOutcome, RetagOptions, TagRename};
+use version_it_core::{CalverFormat, Config, CommitClassification, Scheme, VersionItError};
+use version_it_core::package::PACKAGE_MANAGERS;
+use version_it_core::templates::{render_version_format, HEADER_PRESETS};
+use super::output::{output_success, paint, Completion, OutputFormat, SKIP_STYLE, SUCCESS_STYLE};
+use std::io::{BufRead, IsTerminal, Write};
+
+#[derive(Debug)]
+pub struct CommandContext {
+    pub config: Option<Config>,
+    pub output_format: OutputFormat,
+    /// Whether missing arguments may be asked for on the terminal.
+    pub interactive: bool,
+}
+
+impl CommandContext {
+    pub fn new(config: Option<Config>, output_format: OutputFormat, non_interactive: bool) -> Self {
+        CommandContext {
+            config,
+            output_format,
+            interactive: !non_interactive && std::io::stdin().is_terminal(),
+        }
+    }
+}
+
+/// Fills in the bump type when `--bump` was omitted, asking on the terminal if allowed.
+fn resolve_bump_type(options: &mut BumpOptions, context: &CommandContext) -> Result<(), VersionItError> {
+    // Releasing a snapshot needs no bump; run_bump reports a missing one otherwise
+    if !options.bump.is_empty() || options.bump_index.is_some() || options.release {
+        return Ok(());
+    }
+    if !context.interactive {
+        return Err(VersionItError::InvalidArgument("No bump type given. Pass --bump major, minor or patch.".to_string()));
+    }
+    let stdin = std::io::stdin();
+    loop {
+        eprint!("Select bump type: [major/minor/patch] ");
+        std::io::stderr().flush().map_err(|e| VersionItError::from(e).context("Error writing prompt"))?;
+        let mut choice = String::new();
+        if stdin.lock().read_line(&mut choice).map_err(|e| VersionItError::from(e).context("Error reading bump type"))? == 0 {
+            return Err(VersionItError::InvalidArgument("No bump type given".to_string()));
+        }
+        let choice = choice.trim().to_lowercase();
+        if ["major", "minor", "patch"].contains(&choice.as_str()) {
+            options.bump = choice;
+            return Ok(());
+        }
+    }
+}
+
+/// Renders `--format` for a version, if given.
+fn format_version(format: Option<&str>, version: &str, previous_version: &str, scheme: &str, channel: Option<&str>) -> Result<Option<String>, VersionItError> {
+    format
+        .map(|f| render_version_format(f, version, previous_version, scheme, channel))
+        .transpose()
+        .map_err(|e| e.context("Error rendering format"))
+}
+
+fn print_explanation(explanation: &[CommitClassification]) {
+    println!("Commit classification:");
+    for classified in explanation {
+        match (&classified.matched_label, &classified.action) {
+            (Some(label), Some(action)) => println!("  {} -> {} ({})", classified.commit, action, label),
+            (Some(label), None) => println!("  {} -> ignored ({})", classified.commit, label),
+            _ => println!("  {} -> no match", classified.commit),
+        }
+    }
+}
+
+/// Reports that no bump is warranted. The process then exits with `EXIT_NO_BUMP_NEEDED`,
+/// so scripts can tell it apart from a bump without parsing the output.
+fn report_no_bump_needed(context: &CommandContext, explanation: Option<&[CommitClassification]>) -> Completion {
+    if context.output_format.is_structured() {
+        let mut data = serde_json::json!({
+            "success": true,
+            "message": "No bump needed"
+        });
+        if let Some(explanation) = explanation {
+            data["commits"] = serde_json::json!(explanation);
+        }
+        output_success(context.output_format, data);
+    } else {
+        anstream::println!("{}", paint(SKIP_STYLE, "No bump needed"));
+        if let Some(explanation) = explanation {
+            print_explanation(explanation);
+        }
+    }
+    Completion::NoBumpNeeded
+}
+
+fn print_bump_result(result: &BumpResult, format: Option<&str>, context: &CommandContext, include_previous: bool, explanation: Option<&[CommitClassification]>) -> Result<(), VersionItError> {
+    let formatted = format_version(format, &result.version, &result.previous_version, &result.scheme, result.channel.as_deref())?;
+    if context.output_format.is_structured() {
+        let mut data = serde_json::json!({
+            "success": true,
+            "version": result.version,
+            "bump_type": result.bump_type
+        });
+        if include_previous {
+            data["previous_version"] = serde_json::json!(result.previous_version);
+        }
+        if let Some(ref formatted) = formatted {
+            data["formatted"] = serde_json::json!(formatted);
+        }
+        if let Some(explanation) = explanation {
+            data["commits"] = serde_json::json!(explanation);
+        }
+        if let Some((ref file_version, ref tag)) = result.divergence {
+            data["version_file"] = serde_json::json!(file_version);
+            data["latest_tag"] = serde_json::json!(tag);
+        }
+        // Stdout must stay a single JSON document, so the plan and git status go in it
+        if result.dry_run {
+            data["dry_run"] = serde_json::json!(true);
+            data["operations"] = serde_json::json!(result.operations);
+            let plan = &result.plan;
+            data["plan"] = serde_json::json!({
+                "version_files": plan.version_files,
+                "headers": plan.headers,
+                "package_files": plan.package_files,
+                "changelogs": plan.changelogs,
+                "commit": plan.commit,
+                "amend": plan.amend,
+                "tag": plan.tag,
+                "lightweight_tag": plan.lightweight_tag,
+                "checksums": plan.checksums
+            });
+        } else {
+            data["committed"] = serde_json::json!(result.committed);
+            data["tagged"] = serde_json::json!(result.tagged);
+        }
+        output_success(context.output_format, data);
+        return Ok(());
+    }
+
+    if let Some((ref file_version, ref tag)) = result.divergence {
+        log::warn!("version file says {} but the latest version tag is {}", file_version, tag);
+    }
+    println!("{}", formatted.as_deref().unwrap_or(&result.version));
+    if let Some(explanation) = explanation {
+        print_explanation(explanation);
+    }
+
+    if result.dry_run {
+        println!("DRY RUN: Would perform the following operations:");
+        for operation in &result.operations {
+            println!("  - {}", operation);
+        }
+    } else {
+        if result.committed {
+            log::info!("Committed version bump: {}", result.version);
+        }
+        if result.tagged {
+            log::info!("Created git tag: {}", result.version);
+        }
+        if result.nothing_to_tag {
+            log::info!("Nothing to tag: no changes were committed for {}", result.version);
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_bump_command(mut options: BumpOptions, format: Option<&str>, context: &CommandContext) -> Result<Completion, VersionItError> {
+    resolve_bump_type(&mut options, context)?;
+    match run_bump(options, context.config.as_ref())? {
+        BumpOutcome::Skipped { branch } => {
+            let message = format!("Skipping bump: branch '{}' is not in run-on-branches", branch);
+            if context.output_format.is_structured() {
+                let data = serde_json::json!({
+                    "success": true,
+                    "skipped": true,
+                    "message": message
+                });
+                output_success(context.output_format, data);
+            } else {
+                anstream::println!("{}", paint(SKIP_STYLE, &message));
+            }
+        }
+        BumpOutcome::NoBumpNeeded => return Ok(report_no_bump_needed(context, None)),
+        BumpOutcome::Bumped(result) => print_bump_result(&result, format, context, true, None)?,
+    }
+    Ok(Completion::Done)
+}
+
+pub fn handle_next_command(mut options: BumpOptions, format: Option<&str>, show_previous: bool, context: &CommandContext) -> Result<(), VersionItError> {
+    resolve_bump_type(&mut options, context)?;
+    let outcome = run_next(options, context.config.as_ref())?;
+    let formatted = format_version(format, &outcome.version, &outcome.previous_version, &outcome.scheme, outcome.channel.as_deref())?;
+    if context.output_format.is_structured() {
+        let mut data = serde_json::json!({
+            "success": true,
+            "version": outcome.version,
+            "previous_version": outcome.previous_version
+        });
+        if let Some(ref formatted) = formatted {
+            data["formatted"] = serde_json::json!(formatted);
+        }
+        output_success(context.output_format, data);
+    } else {
+        let version = formatted.as_deref().unwrap_or(&outcome.version);
+        if show_previous {
+            println!("{} {}", outcome.previous_version, version);
+        } else {
+            println!("{}", version);
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_auto_bump_command(options: AutoBumpOptions, context: &CommandContext) -> Result<Completion, VersionItError> {
+    let cfg = context.config.as_ref().ok_or_else(|| VersionItError::ConfigNotFound("No config found for auto-bump".to_string()))?;
+    if options.check {
+        let bump_type = check_auto_bump(&options, cfg)?;
+        if context.output_format.is_structured() {
+            output_success(context.output_format, serde_json::json!({
+                "success": true,
+                "bump_type": bump_type
+            }));
+        } else {
+            println!("{}", bump_type.as_deref().unwrap_or("none"));
+        }
+        return Ok(Completion::Done);
+    }
+    let explanation = if options.explain || options.dry_run {
+        let analyzed = Config { commit_analysis_first_parent: options.first_parent || cfg.commit_analysis_first_parent, ..cfg.clone() };
+        Some(analyzed.explain_commits_for_bump_since(options.since.as_deref()).map_err(|e| e.context("Error analyzing commits"))?)
+    } else {
+        None
+    };
+    match run_auto_bump(options, cfg)? {
+        AutoBumpOutcome::NoBumpNeeded => return Ok(report_no_bump_needed(context, explanation.as_deref())),
+        AutoBumpOutcome::Bumped(result) => print_bump_result(&result, None, context, false, explanation.as_deref())?,
+    }
+    Ok(Completion::Done)
+}
+
+pub fn handle_diff_command(mut options: BumpOptions, context: &CommandContext) -> Result<(), VersionItError> {
+    resolve_bump_type(&mut options, context)?;
+    let outcome = run_diff(options, context.config.as_ref())?;
+    if context.output_format.is_structured() {
+        let headers: Vec<serde_json::Value> = outcome.headers.iter()
+            .map(|h| serde_json::json!({ "path": h.path, "diff": h.diff }))
+            .collect();
+        let data = serde_json::json!({
+            "success": true,
+            "previous_version": outcome.previous_version,
+            "version": outcome.version,
+            "headers": headers
+        });
+        output_success(context.output_format, data);
+        return Ok(());
+    }
+
+    for header in &outcome.headers {
+        if header.diff.is_empty() {
+            log::info!("{} is unchanged", header.path);
+        } else {
+            print!("{}", header.diff);
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_retag_command(options: RetagOptions, context: &CommandContext) -> Result<(), VersionItError> {
+    let outcome = run_retag(options, context.config.as_ref())?;
+    if context.output_format.is_structured() {
+        let pairs = |renames: &[TagRename]| -> Vec<serde_json::Value> {
+            renames.iter().map(|r| serde_json::json!({ "from": r.from, "to": r.to })).collect()
+        };
+        let data = serde_json::json!({
+            "success": true,
+            "applied": outcome.applied,
+            "renames": pairs(&outcome.renames),
+            "conflicts": pairs(&outcome.conflicts)
+        });
+        output_success(context.output_format, data);
+        return Ok(());
+    }
+
+    if outcome.renames.is_empty() && outcome.conflicts.is_empty() {
+        println!("No version tags to rename");
+    }
+    for rename in &outcome.renames {
+        if outcome.applied {
+            anstream::println!("{}", paint(SUCCESS_STYLE, &format!("Renamed {} -> {}", rename.from, rename.to)));
+        } else {
+            println!("Would rename {} -> {}", rename.from, rename.to);
+        }
+    }
+    for conflict in &outcome.conflicts {
+        log::warn!("skipping {}: tag {} already exists", conflict.from, conflict.to);
+    }
+    if !outcome.applied && !outcome.renames.is_empty() {
+        log::info!("Pass --apply to rename the tags");
+    }
+    Ok(())
+}
+
+pub fn handle_schema_command() -> Result<(), VersionItError> {
+    let schema = serde_json::to_string_pretty(&Config::json_schema())
+        .map_err(|e| VersionItError::Config(format!("Error serializing schema: {}", e)))?;
+    println!("{}", schema);
+    Ok(())
+}
+
+/// Prints the config in effect, after environment expansion and defaults, with the
+/// directory its relative paths resolve against as `base-path`.
+pub fn handle_config_command(context: &CommandContext) -> Result<(), VersionItError> {
+    let config = context.config.as_ref().ok_or_else(|| VersionItError::ConfigNotFound("No config found".to_string()))?;
+    let mut value = serde_json::to_value(config).map_err(|e| VersionItError::Config(format!("Error serializing config: {}", e)))?;
+    let base_path = match config.base_path {
+        Some(ref base_path) => base_path.clone(),
+        None => std::env::current_dir().map_err(|e| VersionItError::from(e).context("Error reading the working directory"))?,
+    };
+    value["base-path"] = serde_json::json!(base_path);
+    if context.output_format.is_structured() {
+        output_success(context.output_format, serde_json::json!({
+            "success": true,
+            "config": value
+        }));
+    } else {
+        let yaml = serde_yaml::to_string(&value).map_err(|e| VersionItError::Config(format!("Error serializing config: {}", e)))?;
+        print!("{}", yaml);
+    }
+    Ok(())
+}
+
+pub fn handle_info_command(context: &CommandContext) -> Result<(), VersionItError> {
+    let schemes: Vec<&str> = Scheme::ALL.iter().map(|s| s.as_str()).collect();
+    let calver_formats: Vec<&str> = CalverFormat::ALL.iter().map(|f| f.as_str()).collect();
+    if context.output_format.is_structured() {
+        let data = serde_json::json!({
+            "success": true,
+            "schemes": schemes,
+            "calver_formats": calver_formats,
+            "package_managers": PACKAGE_MANAGERS,
+            "header_presets": HEADER_PRESETS
+        });
+        output_success(context.output_format, data);
+    } else {
+        println!("Versioning schemes: {}", schemes.join(", "));
+        println!("Calver formats: {}", calver_formats.join(", "));
+        println!("Package managers: {}", PACKAGE_MANAGERS.join(", "));
+        println!("Header presets: {}", HEADER_PRESETS.join(", "));
+    }
+    Ok(())
+}
@@ -0,0 +1,12 @@
+//! Command implementations behind the `version-it` binary.
+//!
+//! These return typed outcomes instead of printing or exiting, so they can be
+//! called from other Rust programs such as `build.rs` scripts.
+
+pub mod commands;
+pub mod git_ops;
+
+pub use commands::{
+    run_bump, run_next, run_auto_bump, check_auto_bump, run_retag, run_diff, BumpOptions, AutoBumpOptions, BumpOutcome, BumpPlan, BumpResult, NextOutcome, AutoBumpOutcome,
+    RetagOptions, RetagOutcome, TagRename, DiffOutcome, HeaderDiff,
+};
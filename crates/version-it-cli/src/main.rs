@@ -1,12 +1,24 @@
 mod output;
-mod commands;
-mod git_ops;
+mod handlers;
 
-use clap::{Parser, Subcommand};
-use version_it_core::Config;
-use std::path::Path;
-use output::output_error;
-use commands::{handle_bump_command, handle_next_command, handle_auto_bump_command, BumpOptions, AutoBumpOptions, CommandContext};
+use clap::builder::PossibleValuesParser;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use version_it_core::{Config, Scheme, VersionItError};
+use std::path::{Path, PathBuf};
+use std::io::Read;
+use output::{init_logging, output_error, Completion, OutputFormat, EXIT_NO_BUMP_NEEDED};
+use handlers::{handle_bump_command, handle_next_command, handle_auto_bump_command, handle_schema_command, handle_info_command, handle_retag_command, handle_diff_command, handle_config_command, CommandContext};
+use version_it_cli::{BumpOptions, AutoBumpOptions, RetagOptions};
+
+/// Values accepted by `--bump`.
+const BUMP_TYPES: [&str; 4] = ["major", "minor", "patch", "auto"];
+
+/// Accepts the names of the supported versioning schemes for `--scheme`.
+fn scheme_parser() -> PossibleValuesParser {
+    PossibleValuesParser::new(Scheme::ALL.map(|scheme| scheme.as_str()))
+}
 
 #[derive(Parser)]
 #[command(name = "version-it")]
@@ -14,12 +26,44 @@ use commands::{handle_bump_command, handle_next_command, handle_auto_bump_comman
 struct Cli {
     #[command(subcommand)]
     command: Commands,
-    /// Path to config file (default: .version-it)
-    #[arg(short, long, default_value = ".version-it")]
+    /// Path to config file. Precedence: this flag, then the VERSION_IT_CONFIG
+    /// environment variable, then .version-it
+    #[arg(short, long, env = "VERSION_IT_CONFIG", default_value = ".version-it")]
     config: String,
-    /// Output responses in structured JSON format
+    /// Read the config (YAML or JSON) from stdin instead of a file; takes precedence
+    /// over VERSION_IT_CONFIG
     #[arg(long)]
+    config_stdin: bool,
+    /// Format of the response on stdout
+    #[arg(long, value_enum)]
+    output_format: Option<OutputFormat>,
+    /// Same as --output-format json
+    #[arg(long, conflicts_with = "output_format")]
     structured_output: bool,
+    /// Only look for the config file in the current directory instead of
+    /// searching parent directories
+    #[arg(long)]
+    no_search: bool,
+    /// Reject unknown keys in the config file instead of ignoring them
+    #[arg(long)]
+    strict: bool,
+    /// Directory that relative paths in the config resolve against
+    /// (default: the directory containing the config file)
+    #[arg(long)]
+    base_path: Option<PathBuf>,
+    /// Never prompt; error out on missing arguments instead
+    #[arg(short, long, alias = "non-interactive")]
+    yes: bool,
+    /// Show debug diagnostics on stderr (-vv for trace)
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Only report errors on stderr
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Never color the output (color is also off when NO_COLOR is set or the output
+    /// is not a terminal)
+    #[arg(long)]
+    no_color: bool,
 }
 
 #[derive(Subcommand)]
@@ -29,21 +73,97 @@ enum Commands {
         /// Current version (optional, uses config first-version if not provided)
         #[arg(short, long)]
         version: Option<String>,
-        /// Bump type: major, minor, patch
-        #[arg(short, long)]
-        bump: String,
+        /// Read the current version from this file (unless --version is given) and
+        /// write the new version back to it; works without a config
+        #[arg(long)]
+        version_file: Option<PathBuf>,
+        /// Also write the new version to this file (repeatable), e.g. to read from
+        /// Cargo.toml but publish deploy/VERSION
+        #[arg(long, value_name = "FILE")]
+        write_to: Vec<PathBuf>,
+        /// Bump type: major, minor, patch, or auto to derive it from commits
+        /// (prompted for on a terminal if omitted)
+        #[arg(short, long, value_parser = BUMP_TYPES)]
+        bump: Option<String>,
+        /// Bump the component at this index instead (0 for major), e.g. 4 for the
+        /// fifth part of 1.0.4.2.7; numeric and build schemes only
+        #[arg(long, conflicts_with = "bump")]
+        bump_index: Option<usize>,
         /// Versioning scheme (optional, uses config or defaults to semantic)
-        #[arg(short, long)]
+        #[arg(short, long, value_parser = scheme_parser())]
         scheme: Option<String>,
         /// Release channel (stable, beta, nightly, or custom)
         #[arg(long)]
         channel: Option<String>,
+        /// Increment for monotonic versions (overrides config monotonic-step)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        bump_by: Option<u64>,
+        /// Set the major component after bumping (resets minor and patch)
+        #[arg(long)]
+        set_major: Option<u64>,
+        /// Set the minor component after bumping (resets patch)
+        #[arg(long)]
+        set_minor: Option<u64>,
+        /// Set the patch component after bumping
+        #[arg(long)]
+        set_patch: Option<u64>,
+        /// Semver prerelease identifier set after bumping, e.g. rc.1
+        #[arg(long)]
+        pre: Option<String>,
+        /// Semver build metadata set after bumping, e.g. sha.abc123
+        #[arg(long)]
+        build: Option<String>,
+        /// Append the snapshot suffix (snapshot-suffix, default -SNAPSHOT) for a
+        /// development build; a suffix on the current version is not doubled
+        #[arg(long, conflicts_with_all = ["pre", "release"])]
+        snapshot: bool,
+        /// Release the current snapshot, e.g. 1.2.3-SNAPSHOT becomes 1.2.3 (--bump is
+        /// only needed when the current version is not a snapshot)
+        #[arg(long)]
+        release: bool,
+        /// Print the version through a handlebars template, e.g. 'v{{version}}'
+        #[arg(long)]
+        format: Option<String>,
+        /// Skip the bump unless the current branch is listed in run-on-branches
+        #[arg(long)]
+        respect_branch: bool,
+        /// Write the new version even if it is lower than the current one
+        #[arg(long)]
+        allow_downgrade: bool,
+        /// With --bump auto, fail if the version file and latest version tag differ
+        #[arg(long)]
+        require_tag_match: bool,
         /// Create a git tag after bumping
         #[arg(long)]
         create_tag: bool,
+        /// Handlebars template for the tag annotation; can use version,
+        /// previous_version, scheme, channel and changelog
+        #[arg(long, requires = "create_tag", conflicts_with = "lightweight")]
+        tag_message: Option<String>,
+        /// Create a lightweight tag instead of an annotated one
+        #[arg(long, requires = "create_tag")]
+        lightweight: bool,
         /// Commit version file changes after bumping
         #[arg(long)]
         commit: bool,
+        /// Skip git hooks (pre-commit, commit-msg) for the release commit
+        #[arg(long, requires = "commit")]
+        no_verify: bool,
+        /// Fold the version changes into the last commit instead of creating one,
+        /// moving a tag of the new version on that commit along
+        #[arg(long, requires = "commit")]
+        amend: bool,
+        /// Amend even when the last commit was already pushed, and move a tag of the
+        /// new version that is on another commit
+        #[arg(long, requires = "amend")]
+        force: bool,
+        /// Hold an exclusive lock on this file for the whole bump, so concurrent
+        /// runs wait for each other instead of bumping the same version twice
+        #[arg(long, value_name = "FILE")]
+        lock: Option<PathBuf>,
+        /// Seconds to wait for --lock before giving up (default 60)
+        #[arg(long, value_name = "SECS", requires = "lock")]
+        lock_timeout: Option<u64>,
         /// Show what would happen without making changes
         #[arg(long)]
         dry_run: bool,
@@ -53,83 +173,338 @@ enum Commands {
         /// Current version (optional, uses config first-version if not provided)
         #[arg(short, long)]
         version: Option<String>,
-        /// Bump type: major, minor, patch
-        #[arg(short, long)]
-        bump: String,
+        /// Read the current version from this file (unless --version is given); works
+        /// without a config
+        #[arg(long)]
+        version_file: Option<PathBuf>,
+        /// Also write the new version to this file (repeatable), e.g. to read from
+        /// Cargo.toml but publish deploy/VERSION
+        #[arg(long, value_name = "FILE")]
+        write_to: Vec<PathBuf>,
+        /// Bump type: major, minor, patch, or auto to derive it from commits
+        /// (prompted for on a terminal if omitted)
+        #[arg(short, long, value_parser = BUMP_TYPES)]
+        bump: Option<String>,
+        /// Bump the component at this index instead (0 for major), e.g. 4 for the
+        /// fifth part of 1.0.4.2.7; numeric and build schemes only
+        #[arg(long, conflicts_with = "bump")]
+        bump_index: Option<usize>,
         /// Versioning scheme (optional, uses config or defaults to semantic)
-        #[arg(short, long)]
+        #[arg(short, long, value_parser = scheme_parser())]
         scheme: Option<String>,
         /// Release channel (stable, beta, nightly, or custom)
         #[arg(long)]
         channel: Option<String>,
+        /// Increment for monotonic versions (overrides config monotonic-step)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        bump_by: Option<u64>,
+        /// Set the major component after bumping (resets minor and patch)
+        #[arg(long)]
+        set_major: Option<u64>,
+        /// Set the minor component after bumping (resets patch)
+        #[arg(long)]
+        set_minor: Option<u64>,
+        /// Set the patch component after bumping
+        #[arg(long)]
+        set_patch: Option<u64>,
+        /// Semver prerelease identifier set after bumping, e.g. rc.1
+        #[arg(long)]
+        pre: Option<String>,
+        /// Semver build metadata set after bumping, e.g. sha.abc123
+        #[arg(long)]
+        build: Option<String>,
+        /// Append the snapshot suffix (snapshot-suffix, default -SNAPSHOT) for a
+        /// development build; a suffix on the current version is not doubled
+        #[arg(long, conflicts_with_all = ["pre", "release"])]
+        snapshot: bool,
+        /// Release the current snapshot, e.g. 1.2.3-SNAPSHOT becomes 1.2.3 (--bump is
+        /// only needed when the current version is not a snapshot)
+        #[arg(long)]
+        release: bool,
+        /// Print the version through a handlebars template, e.g. 'v{{version}}'
+        #[arg(long)]
+        format: Option<String>,
+        /// Print the current and next version separated by a space
+        #[arg(long)]
+        show_previous: bool,
     },
-    /// Automatically bump version based on commits
+    /// Automatically bump version based on commits (same as bump --bump auto)
     AutoBump {
         /// Create a git tag after bumping
         #[arg(long)]
         create_tag: bool,
+        /// Handlebars template for the tag annotation; can use version,
+        /// previous_version, scheme, channel and changelog
+        #[arg(long, requires = "create_tag", conflicts_with = "lightweight")]
+        tag_message: Option<String>,
+        /// Create a lightweight tag instead of an annotated one
+        #[arg(long, requires = "create_tag")]
+        lightweight: bool,
         /// Commit version file changes after bumping
         #[arg(long)]
         commit: bool,
+        /// Skip git hooks (pre-commit, commit-msg) for the release commit
+        #[arg(long, requires = "commit")]
+        no_verify: bool,
+        /// Fold the version changes into the last commit instead of creating one,
+        /// moving a tag of the new version on that commit along
+        #[arg(long, requires = "commit")]
+        amend: bool,
+        /// Amend even when the last commit was already pushed, and move a tag of the
+        /// new version that is on another commit
+        #[arg(long, requires = "amend")]
+        force: bool,
+        /// Hold an exclusive lock on this file for the whole bump, so concurrent
+        /// runs wait for each other instead of bumping the same version twice
+        #[arg(long, value_name = "FILE")]
+        lock: Option<PathBuf>,
+        /// Seconds to wait for --lock before giving up (default 60)
+        #[arg(long, value_name = "SECS", requires = "lock")]
+        lock_timeout: Option<u64>,
         /// Show what would happen without making changes
         #[arg(long)]
         dry_run: bool,
+        /// Show which change-type-map entry each commit matched
+        #[arg(long)]
+        explain: bool,
+        /// Fail if the version file and latest version tag differ
+        #[arg(long)]
+        require_tag_match: bool,
+        /// Analyze the commits after this ref instead of those after the latest
+        /// version tag, e.g. the root commit for a first release
+        #[arg(long)]
+        since: Option<String>,
+        /// Highest bump to apply (major, minor or patch); larger bumps are lowered
+        /// to it with a warning. Overrides max-auto-bump
+        #[arg(long, value_parser = ["major", "minor", "patch"])]
+        max_bump: Option<String>,
+        /// Only analyze first-parent commits, so a merged branch counts as its merge
+        /// commit. Same as commit-analysis-first-parent in the config
+        #[arg(long)]
+        first_parent: bool,
+        /// Only print the bump the commits call for (major, minor, patch or none),
+        /// without touching version files, headers or git
+        #[arg(long, conflicts_with_all = ["create_tag", "commit", "dry_run", "explain", "lock"])]
+        check: bool,
+    },
+    /// Re-create version tags such as v1.2.3 or release-1.2.4 under a normalized name
+    /// (dry run unless --apply is given)
+    Retag {
+        /// Handlebars template for the new tag names, e.g. 'v{{version}}'
+        /// (default '{{version}}')
+        #[arg(long)]
+        format: Option<String>,
+        /// Delete each old tag after creating its replacement
+        #[arg(long)]
+        delete_old: bool,
+        /// Create (and delete) the tags instead of only listing the renames
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Show how the generated headers would change between the current and next
+    /// version, as a unified diff (nothing is written)
+    Diff {
+        /// Current version (optional, uses config first-version if not provided)
+        #[arg(short, long)]
+        version: Option<String>,
+        /// Bump type: major, minor, patch, or auto to derive it from commits
+        /// (prompted for on a terminal if omitted)
+        #[arg(short, long, value_parser = BUMP_TYPES)]
+        bump: Option<String>,
+        /// Versioning scheme (optional, uses config or defaults to semantic)
+        #[arg(short, long, value_parser = scheme_parser())]
+        scheme: Option<String>,
+        /// Release channel (stable, beta, nightly, or custom)
+        #[arg(long)]
+        channel: Option<String>,
     },
+    /// Print the JSON Schema of the config file format
+    Schema,
+    /// List the supported versioning schemes, package managers and header presets
+    Info,
+    /// Print the config in effect, after environment expansion and defaults, with the
+    /// resolved base-path (YAML, or a document with --output-format)
+    #[command(alias = "print-config")]
+    Config,
 }
 
 
 
 fn main() {
-    let cli = Cli::parse();
-    let config = if Path::new(&cli.config).exists() {
-        let c = Config::load_from_file(&cli.config);
-        if c.is_err() {
-            output_error(cli.structured_output, &format!("Error loading config: {}", c.err().unwrap()));
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // Only an explicit --config conflicts with --config-stdin, not VERSION_IT_CONFIG
+    if cli.config_stdin && matches.value_source("config") == Some(ValueSource::CommandLine) {
+        Cli::command()
+            .error(ErrorKind::ArgumentConflict, "the argument '--config-stdin' cannot be used with '--config <CONFIG>'")
+            .exit();
+    }
+    if cli.no_color {
+        anstream::ColorChoice::Never.write_global();
+    }
+    init_logging(cli.verbose, cli.quiet);
+    let cli_output_format = cli.output_format.or(cli.structured_output.then_some(OutputFormat::Json));
+    let config_path = if Path::new(&cli.config).exists() {
+        Some(PathBuf::from(&cli.config))
+    } else if !cli.no_search && Path::new(&cli.config).is_relative() {
+        Config::find_config_file(&cli.config)
+    } else {
+        None
+    };
+    let config = if cli.config_stdin {
+        let mut contents = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut contents) {
+            let e = VersionItError::from(e).context("Error reading config from stdin");
+            output_error(cli_output_format.unwrap_or(OutputFormat::Text), e.code(), &e.to_string());
         }
-        Some(c.unwrap())
+        match Config::load_from_str(&contents, cli.strict) {
+            Ok(mut c) => {
+                c.base_path = cli.base_path.clone();
+                Some(c)
+            }
+            Err(e) => output_error(cli_output_format.unwrap_or(OutputFormat::Text), e.code(), &format!("Error loading config: {}", e)),
+        }
+    } else if let Some(config_path) = config_path {
+        log::debug!("Loading config from {}", config_path.display());
+        let c = if cli.strict {
+            Config::load_from_file_strict(&config_path.to_string_lossy())
+        } else {
+            Config::load_from_file(&config_path.to_string_lossy())
+        };
+        let mut c = match c {
+            Ok(c) => c,
+            Err(e) => output_error(cli_output_format.unwrap_or(OutputFormat::Text), e.code(), &format!("Error loading config: {}", e)),
+        };
+        if let Some(ref base_path) = cli.base_path {
+            c.base_path = Some(base_path.clone());
+        }
+        Some(c)
     } else {
+        log::debug!("No config file found");
         None
     };
 
-    let structured_output = cli.structured_output || config.as_ref().map(|c| c.structured_output).unwrap_or(false);
+    // `structured-output: true` in the config means JSON unless the command line says otherwise
+    let config_output_format = config.as_ref().filter(|c| c.structured_output).map(|_| OutputFormat::Json);
+    let output_format = cli_output_format.or(config_output_format).unwrap_or(OutputFormat::Text);
 
-    let context = CommandContext {
-        config,
-        structured_output,
-    };
+    let context = CommandContext::new(config, output_format, cli.yes);
 
-    match cli.command {
-        Commands::Bump { version, bump, scheme, channel, create_tag, commit, dry_run } => {
+    let result = match cli.command {
+        Commands::Bump { version, version_file, write_to, bump, bump_index, scheme, channel, bump_by, set_major, set_minor, set_patch, pre, build, snapshot, release, format, respect_branch, allow_downgrade, require_tag_match, create_tag, tag_message, lightweight, commit, no_verify, amend, force, lock, lock_timeout, dry_run } => {
             let options = BumpOptions {
                 version,
-                bump,
+                version_file,
+                write_to,
+                bump: bump.unwrap_or_default(),
+                bump_index,
                 scheme,
                 channel,
+                bump_by,
+                set_major,
+                set_minor,
+                set_patch,
+                pre,
+                build,
+                snapshot,
+                release,
+                respect_branch,
+                allow_downgrade,
+                require_tag_match,
+                since: None,
+                max_bump: None,
+                first_parent: false,
                 create_tag,
+                tag_message,
+                lightweight,
                 commit,
+                no_verify,
+                amend,
+                force,
+                lock,
+                lock_timeout,
                 dry_run,
             };
-            handle_bump_command(options, &context);
+            handle_bump_command(options, format.as_deref(), &context)
         }
-        Commands::Next { version, bump, scheme, channel } => {
+        Commands::Next { version, version_file, write_to, bump, bump_index, scheme, channel, bump_by, set_major, set_minor, set_patch, pre, build, snapshot, release, format, show_previous } => {
             let options = BumpOptions {
                 version,
-                bump,
+                version_file,
+                write_to,
+                bump: bump.unwrap_or_default(),
+                bump_index,
                 scheme,
                 channel,
+                bump_by,
+                set_major,
+                set_minor,
+                set_patch,
+                pre,
+                build,
+                snapshot,
+                release,
+                respect_branch: false,
+                allow_downgrade: false,
+                require_tag_match: false,
+                since: None,
+                max_bump: None,
+                first_parent: false,
                 create_tag: false,
+                tag_message: None,
+                lightweight: false,
                 commit: false,
+                no_verify: false,
+                amend: false,
+                force: false,
+                lock: None,
+                lock_timeout: None,
                 dry_run: false,
             };
-            handle_next_command(options, &context);
+            handle_next_command(options, format.as_deref(), show_previous, &context).map(|()| Completion::Done)
         }
-        Commands::AutoBump { create_tag, commit, dry_run } => {
+        Commands::AutoBump { create_tag, tag_message, lightweight, commit, no_verify, amend, force, lock, lock_timeout, dry_run, explain, require_tag_match, since, max_bump, first_parent, check } => {
             let options = AutoBumpOptions {
                 create_tag,
+                tag_message,
+                lightweight,
                 commit,
+                no_verify,
+                amend,
+                force,
                 dry_run,
+                explain,
+                require_tag_match,
+                since,
+                max_bump,
+                first_parent,
+                check,
+                lock,
+                lock_timeout,
+            };
+            handle_auto_bump_command(options, &context)
+        }
+        Commands::Retag { format, delete_old, apply } => {
+            handle_retag_command(RetagOptions { format, delete_old, apply }, &context).map(|()| Completion::Done)
+        }
+        Commands::Diff { version, bump, scheme, channel } => {
+            let options = BumpOptions {
+                version,
+                bump: bump.unwrap_or_default(),
+                scheme,
+                channel,
+                ..Default::default()
             };
-            handle_auto_bump_command(options, &context);
+            handle_diff_command(options, &context).map(|()| Completion::Done)
         }
+        Commands::Schema => handle_schema_command().map(|()| Completion::Done),
+        Commands::Info => handle_info_command(&context).map(|()| Completion::Done),
+        Commands::Config => handle_config_command(&context).map(|()| Completion::Done),
+    };
+
+    match result {
+        Ok(Completion::Done) => {}
+        Ok(Completion::NoBumpNeeded) => std::process::exit(EXIT_NO_BUMP_NEEDED),
+        Err(e) => output_error(context.output_format, e.code(), &e.to_string()),
     }
 }
\ No newline at end of file
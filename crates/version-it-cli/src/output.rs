@@ -1,6 +1,92 @@
-pub fn output_success(structured: bool, data: serde_json::Value) {
-    if structured {
-        println!("{}", serde_json::to_string(&data).unwrap());
+use anstyle::{AnsiColor, Style};
+use std::io::Write;
+
+/// Exit code when an automatic bump found nothing to release.
+pub const EXIT_NO_BUMP_NEEDED: i32 = 3;
+
+/// How a command that succeeded ends; `main` turns it into the exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completion {
+    Done,
+    /// An automatic bump found nothing to release (`EXIT_NO_BUMP_NEEDED`).
+    NoBumpNeeded,
+}
+
+/// Style of errors.
+pub const ERROR_STYLE: Style = AnsiColor::Red.on_default();
+/// Style of warnings and skipped work.
+pub const SKIP_STYLE: Style = AnsiColor::Yellow.on_default();
+/// Style of completed work.
+pub const SUCCESS_STYLE: Style = AnsiColor::Green.on_default();
+
+/// Wraps text in the escape codes of a style.
+///
+/// Printed through `anstream`, the codes are stripped unless the stream is a terminal,
+/// and always with `NO_COLOR` or `--no-color`.
+pub fn paint(style: Style, text: &str) -> String {
+    format!("{}{}{}", style.render(), text, style.render_reset())
+}
+
+/// Sets up logging to stderr, so stdout only carries the version or JSON output.
+///
+/// Informational messages are shown by default; `verbose` raises the level to debug
+/// (1) or trace (2+), and `quiet` limits it to errors. `RUST_LOG` refines the filter.
+pub fn init_logging(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => log::LevelFilter::Error,
+        (false, 0) => log::LevelFilter::Info,
+        (false, 1) => log::LevelFilter::Debug,
+        (false, _) => log::LevelFilter::Trace,
+    };
+    // Dependencies only get to report problems
+    env_logger::Builder::new()
+        .filter_level(level.min(log::LevelFilter::Warn))
+        .filter_module("version_it", level)
+        .filter_module("version_it_cli", level)
+        .filter_module("version_it_core", level)
+        .parse_default_env()
+        .format(|buf, record| match record.level() {
+            log::Level::Info => writeln!(buf, "{}", record.args()),
+            log::Level::Warn => writeln!(buf, "{} {}", paint(SKIP_STYLE, "Warning:"), record.args()),
+            log::Level::Error => writeln!(buf, "{} {}", paint(ERROR_STYLE, "Error:"), record.args()),
+            level => writeln!(buf, "[{}] {}", level.as_str().to_lowercase(), record.args()),
+        })
+        .target(env_logger::Target::Pipe(Box::new(anstream::stderr())))
+        .init();
+}
+
+/// Format of the response printed on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Just the version or message, for humans and shell scripts
+    Text,
+    /// Compact JSON on a single line
+    Json,
+    /// Indented JSON
+    JsonPretty,
+    /// YAML
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Whether responses are printed as a document rather than plain text.
+    pub fn is_structured(self) -> bool {
+        self != OutputFormat::Text
+    }
+}
+
+/// Prints a response document in a structured output format.
+fn print_document(format: OutputFormat, data: &serde_json::Value) {
+    match format {
+        OutputFormat::JsonPretty => println!("{}", serde_json::to_string_pretty(data).unwrap()),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(data).unwrap()),
+        _ => println!("{}", serde_json::to_string(data).unwrap()),
+    }
+}
+
+pub fn output_success(format: OutputFormat, data: serde_json::Value) {
+    if format.is_structured() {
+        print_document(format, &data);
     } else if let Some(version) = data.get("version") {
         println!("{}", version.as_str().unwrap());
     } else if let Some(message) = data.get("message") {
@@ -8,16 +94,18 @@ pub fn output_success(structured: bool, data: serde_json::Value) {
     }
 }
 
-pub fn output_error(structured: bool, error: &str) -> ! {
-    if structured {
+/// Reports an error and exits with status 1; structured output includes its `code`.
+pub fn output_error(format: OutputFormat, code: &str, error: &str) -> ! {
+    if format.is_structured() {
         let data = serde_json::json!({
             "success": false,
+            "code": code,
             "error": error
         });
-        println!("{}", serde_json::to_string(&data).unwrap());
+        print_document(format, &data);
         std::process::exit(1);
     } else {
-        eprintln!("{}", error);
+        anstream::eprintln!("{}", paint(ERROR_STYLE, error));
         std::process::exit(1);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,188 @@
+use version_it_cli::{run_bump, run_next, BumpOptions, BumpOutcome, BumpPlan};
+use version_it_core::VersionItError;
+
+fn bump_options(version: &str, bump: &str) -> BumpOptions {
+    BumpOptions {
+        version: Some(version.to_string()),
+        bump: bump.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_run_next() {
+    let outcome = run_next(bump_options("1.2.3", "minor"), None).unwrap();
+    assert_eq!(outcome.previous_version, "1.2.3");
+    assert_eq!(outcome.version, "1.3.0");
+}
+
+#[test]
+fn test_run_bump_dry_run() {
+    let mut options = bump_options("1.2.3", "major");
+    options.dry_run = true;
+    options.create_tag = true;
+    match run_bump(options, None).unwrap() {
+        BumpOutcome::Bumped(result) => {
+            assert_eq!(result.version, "2.0.0");
+            assert!(!result.tagged);
+            assert_eq!(result.operations, vec!["Create git tag '2.0.0'".to_string()]);
+        }
+        other => panic!("bump should have been applied, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_run_bump_invalid_bump_type() {
+    let result = run_bump(bump_options("1.2.3", "huge"), None);
+    assert!(matches!(result, Err(VersionItError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_run_next_with_pre_and_build() {
+    let mut options = bump_options("1.2.3", "minor");
+    options.pre = Some("rc.1".to_string());
+    options.build = Some("sha.abc123".to_string());
+    let outcome = run_next(options, None).unwrap();
+    assert_eq!(outcome.version, "1.3.0-rc.1+sha.abc123");
+}
+
+#[test]
+fn test_run_next_pre_rejected() {
+    let mut options = bump_options("1.2.3", "minor");
+    options.pre = Some("rc.1".to_string());
+    options.channel = Some("beta".to_string());
+    assert!(run_next(options, None).is_err());
+
+    let mut options = bump_options("42", "patch");
+    options.scheme = Some("monotonic".to_string());
+    options.build = Some("456".to_string());
+    assert!(run_next(options, None).is_err());
+
+    let mut options = bump_options("1.2.3", "patch");
+    options.pre = Some("rc..1".to_string());
+    assert!(run_next(options, None).is_err());
+}
+
+#[test]
+fn test_run_next_snapshot_and_release() {
+    let next = |version: &str, bump: &str, snapshot: bool, release: bool| {
+        let mut options = bump_options(version, bump);
+        options.snapshot = snapshot;
+        options.release = release;
+        run_next(options, None)
+    };
+    assert_eq!(next("1.2.3", "patch", true, false).unwrap().version, "1.2.4-SNAPSHOT");
+    assert_eq!(next("1.2.4-SNAPSHOT", "minor", true, false).unwrap().version, "1.3.0-SNAPSHOT");
+    assert_eq!(next("1.2.4-SNAPSHOT", "", false, true).unwrap().version, "1.2.4");
+    assert_eq!(next("1.2.4", "patch", false, true).unwrap().version, "1.2.5");
+    assert!(next("1.2.4", "", false, true).unwrap_err().to_string().contains("No bump type given"));
+    assert!(next("42", "patch", true, false).is_err());
+}
+
+#[test]
+fn test_run_bump_auto_requires_config() {
+    let result = run_bump(bump_options("1.2.3", "auto"), None);
+    assert!(result.unwrap_err().to_string().contains("--bump auto requires a config"));
+}
+
+#[test]
+fn test_run_bump_refuses_downgrade() {
+    let mut options = bump_options("2.3.0", "patch");
+    options.set_major = Some(1);
+    options.dry_run = true;
+    assert!(run_bump(options, None).unwrap_err().to_string().contains("Refusing to downgrade from 2.3.0 to 1.0.0"));
+
+    let mut options = bump_options("2.3.0", "patch");
+    options.set_major = Some(1);
+    options.dry_run = true;
+    options.allow_downgrade = true;
+    assert!(run_bump(options, None).is_ok());
+}
+
+#[test]
+fn test_run_bump_refuses_stale_version() {
+    let path = "test_stale_version_file.txt";
+    std::fs::write(path, "1.5.0").unwrap();
+    let mut options = bump_options("1.2.0", "patch");
+    options.version_file = Some(path.into());
+    options.dry_run = true;
+    let result = run_bump(options, None);
+    std::fs::remove_file(path).unwrap();
+    assert!(result.unwrap_err().to_string().contains("from 1.5.0 to 1.2.1"));
+}
+
+#[test]
+fn test_run_bump_dry_run_plan() {
+    let path = "test_plan_version_file.txt";
+    std::fs::write(path, "1.2.3").unwrap();
+    let mut options = bump_options("1.2.3", "minor");
+    options.version_file = Some(path.into());
+    options.commit = true;
+    options.create_tag = true;
+    options.lightweight = true;
+    options.dry_run = true;
+    let outcome = run_bump(options, None);
+    std::fs::remove_file(path).unwrap();
+    match outcome.unwrap() {
+        BumpOutcome::Bumped(result) => assert_eq!(result.plan, BumpPlan {
+            version_files: vec![path.to_string()],
+            commit: true,
+            tag: Some("1.3.0".to_string()),
+            lightweight_tag: true,
+            ..Default::default()
+        }),
+        other => panic!("bump should have been applied, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_write_to_extra_file() {
+    let source = "test_write_to_source.txt";
+    let target = "test_write_to_target.txt";
+    std::fs::write(source, "1.2.3").unwrap();
+    let mut options = bump_options("1.2.3", "minor");
+    options.version = None;
+    options.version_file = Some(source.into());
+    options.write_to = vec![target.into()];
+    let next = run_next(options, None);
+    let source_contents = std::fs::read_to_string(source).unwrap();
+    let target_contents = std::fs::read_to_string(target).unwrap();
+
+    let mut options = bump_options("1.3.0", "patch");
+    options.write_to = vec![target.into()];
+    let bumped = run_bump(options, None);
+    let bumped_contents = std::fs::read_to_string(target).unwrap();
+    std::fs::remove_file(source).unwrap();
+    std::fs::remove_file(target).unwrap();
+
+    assert_eq!(next.unwrap().version, "1.3.0");
+    assert_eq!(source_contents, "1.2.3");
+    assert_eq!(target_contents, "1.3.0");
+    match bumped.unwrap() {
+        BumpOutcome::Bumped(result) => assert_eq!(result.plan.version_files, vec![target.to_string()]),
+        other => panic!("bump should have been applied, got {:?}", other),
+    }
+    assert_eq!(bumped_contents, "1.3.1");
+}
+
+#[test]
+fn test_run_bump_waits_for_lock() {
+    use fs2::FileExt;
+    let lock_path = "test_bump_lock.lock";
+    let held = std::fs::File::create(lock_path).unwrap();
+    held.lock_exclusive().unwrap();
+    let mut options = bump_options("1.2.3", "patch");
+    options.lock = Some(lock_path.into());
+    options.lock_timeout = Some(0);
+    let contended = run_bump(options, None);
+
+    held.unlock().unwrap();
+    let mut options = bump_options("1.2.3", "patch");
+    options.lock = Some(lock_path.into());
+    options.lock_timeout = Some(0);
+    let acquired = run_bump(options, None);
+    std::fs::remove_file(lock_path).unwrap();
+
+    assert!(contended.unwrap_err().to_string().contains("Timed out after 0s waiting for the lock on test_bump_lock.lock"));
+    assert!(acquired.is_ok());
+}
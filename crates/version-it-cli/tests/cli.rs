@@ -1,9 +1,96 @@
-use std::process::Command;
+#![allow(clippy::needless_borrows_for_generic_args, clippy::useless_format)]
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// The settings every test config needs; `Scratch::with_config` overrides and
+/// extends them.
+const BASE_CONFIG: &str = r#"
+run-on-branches: ["main", "master"]
+versioning-scheme: semantic
+first-version: "1.0.0"
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#;
+
+/// A scratch git repository for one test under the system temp directory, removed
+/// again when the test ends, whether it passed or not.
+struct Scratch {
+    root: PathBuf,
+}
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("version-it-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::create_dir_all(&root).unwrap();
+        let scratch = Scratch { root };
+        scratch.git(&["init", "-q"]);
+        scratch
+    }
+
+    /// Like `new`, with a `.version-it` made of `BASE_CONFIG` and `settings`, which
+    /// replace base settings of the same name.
+    fn with_config(name: &str, settings: &str) -> Self {
+        let mut config: serde_yaml::Mapping = serde_yaml::from_str(BASE_CONFIG).unwrap();
+        if let serde_yaml::Value::Mapping(settings) = serde_yaml::from_str(settings).unwrap() {
+            config.extend(settings);
+        }
+        let scratch = Self::new(name);
+        scratch.write(".version-it", &serde_yaml::to_string(&config).unwrap());
+        scratch
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.root.join(file)
+    }
+
+    fn write(&self, file: &str, contents: &str) {
+        let path = self.path(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn read(&self, file: &str) -> String {
+        std::fs::read_to_string(self.path(file)).unwrap()
+    }
+
+    /// A command run in the scratch directory, with a fixed identity for the git
+    /// commits and tags it creates.
+    fn command(&self, program: &str) -> Command {
+        let mut command = Command::new(program);
+        command
+            .current_dir(&self.root)
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com");
+        command
+    }
+
+    fn git(&self, args: &[&str]) -> Output {
+        self.command("git").args(args).output().expect("Failed to run git")
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        self.command(env!("CARGO_BIN_EXE_version-it")).args(args).output().expect("Failed to run command")
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.root).ok();
+    }
+}
 
 #[test]
 fn test_cli_bump_patch() {
     let output = Command::new("cargo")
-        .args(&["run", "--bin", "version-it", "--", "bump", "--version", "1.0.0", "--bump", "patch"])
+        .args(&["run", "--bin", "version-it", "--", "--no-search", "bump", "--version", "1.0.0", "--bump", "patch"])
         .output()
         .expect("Failed to run command");
 
@@ -15,7 +102,7 @@ fn test_cli_bump_patch() {
 #[test]
 fn test_cli_next_minor() {
     let output = Command::new("cargo")
-        .args(&["run", "--bin", "version-it", "--", "next", "--version", "1.0.0", "--bump", "minor"])
+        .args(&["run", "--bin", "version-it", "--", "--no-search", "next", "--version", "1.0.0", "--bump", "minor"])
         .output()
         .expect("Failed to run command");
 
@@ -27,7 +114,7 @@ fn test_cli_next_minor() {
 #[test]
 fn test_cli_bump_with_scheme() {
     let output = Command::new("cargo")
-        .args(&["run", "--bin", "version-it", "--", "bump", "--version", "1.2.3.4", "--scheme", "build", "--bump", "patch"])
+        .args(&["run", "--bin", "version-it", "--", "--no-search", "bump", "--version", "1.2.3.4", "--scheme", "build", "--bump", "patch"])
         .output()
         .expect("Failed to run command");
 
@@ -94,4 +181,790 @@ enable-expensive-metrics: false
     fs::remove_file(config_path).unwrap();
     fs::remove_file(version_file).unwrap();
     fs::remove_file(header_file).unwrap();
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_cli_bump_monotonic_with_step() {
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "next", "--version", "100", "--scheme", "monotonic", "--bump", "patch", "--bump-by", "10"])
+        .output()
+        .expect("Failed to run command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "110");
+}
+
+#[test]
+fn test_cli_next_with_set_major() {
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "next", "--version", "1.4.2", "--bump", "patch", "--set-major", "2"])
+        .output()
+        .expect("Failed to run command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "2.0.0");
+}
+
+#[test]
+fn test_config_from_env_var() {
+    let repo = Scratch::with_config("env_config", "versioning-scheme: monotonic\nfirst-version: \"41\"");
+    repo.write("sub/.keep", "");
+
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "next", "--bump", "patch"])
+        .env("VERSION_IT_CONFIG", "../.version-it")
+        .current_dir(repo.path("sub"))
+        .output()
+        .expect("Failed to run command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "42");
+}
+#[test]
+fn test_print_config() {
+    let repo = Scratch::with_config("print_config", "first-version: ${TEST_PRINT_CONFIG_VERSION:-1.0.0}");
+    let config_path = repo.path(".version-it");
+
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--config", config_path.to_str().unwrap(), "--output-format", "json", "config"])
+        .env("TEST_PRINT_CONFIG_VERSION", "2.5.0")
+        .output()
+        .expect("Failed to run command");
+    let yaml_output = repo.run(&["--config", config_path.to_str().unwrap(), "print-config"]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let data: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(data["config"]["first-version"], "2.5.0");
+    assert_eq!(data["config"]["version-source"], "file");
+    assert_eq!(data["config"]["base-path"], repo.root.to_str().unwrap());
+    assert!(String::from_utf8_lossy(&yaml_output.stdout).contains("first-version: 1.0.0"));
+}
+
+#[test]
+fn test_config_found_in_parent_directory() {
+    let repo = Scratch::with_config("search_root", "current-version-file: version.txt");
+    repo.write("version.txt", "2.0.0");
+    repo.write("nested/deeper/.keep", "");
+
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_version-it"))
+        .args(["bump", "--bump", "minor"])
+        .current_dir(repo.path("nested/deeper"))
+        .output()
+        .expect("Failed to run command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "2.1.0");
+    assert_eq!(repo.read("version.txt").trim(), "2.1.0");
+    assert!(!repo.path("nested/deeper/version.txt").exists());
+}
+
+#[test]
+fn test_bump_skipped_on_disallowed_branch() {
+    let repo = Scratch::with_config("disallowed_branch", r#"
+run-on-branches: ["no-such-release-branch"]
+current-version-file: version.txt
+enforce-branches: true
+"#);
+    repo.write("version.txt", "1.0.0");
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+
+    let output = repo.run(&["bump", "--bump", "patch"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Skipping bump"));
+    assert_eq!(repo.read("version.txt").trim(), "1.0.0");
+}
+#[test]
+fn test_cli_next_with_format() {
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "next", "--version", "1.2.3", "--bump", "minor", "--format", "v{{version}} (from {{previous_version}})"])
+        .output()
+        .expect("Failed to run command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "v1.3.0 (from 1.2.3)");
+}
+
+#[test]
+fn test_cli_next_show_previous() {
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "next", "--version", "1.2.3", "--bump", "patch", "--show-previous"])
+        .output()
+        .expect("Failed to run command");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "1.2.3 1.2.4");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "--structured-output", "next", "--version", "1.2.3", "--bump", "patch"])
+        .output()
+        .expect("Failed to run command");
+    let data: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(data["previous_version"], "1.2.3");
+    assert_eq!(data["version"], "1.2.4");
+}
+
+#[test]
+fn test_bump_appends_to_changelog() {
+    let repo = Scratch::with_config("append_changelog", r#"
+changelog-exporters:
+  template-path: changelog.hbs
+  output-path: CHANGELOG.md
+changelog-mode: append
+"#);
+    repo.write("changelog.hbs", "## {{version}}\n");
+    repo.write("CHANGELOG.md", "# Changelog\n\n## 1.0.0\n");
+
+    let output = repo.run(&["bump", "--version", "1.0.0", "--bump", "minor"]);
+
+    assert!(output.status.success());
+    assert_eq!(repo.read("CHANGELOG.md"), "# Changelog\n\n## 1.0.0\n\n## 1.1.0\n");
+}
+#[test]
+fn test_cli_schema() {
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "schema"])
+        .output()
+        .expect("Failed to run command");
+
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(schema["properties"]["versioning-scheme"]["enum"].is_array());
+}
+
+#[test]
+fn test_cli_missing_bump_without_terminal() {
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "--yes", "next", "--version", "1.2.3"])
+        .output()
+        .expect("Failed to run command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No bump type given"));
+}
+
+#[test]
+fn test_no_tag_when_nothing_committed() {
+    let repo = Scratch::with_config("nothing_to_tag", "current-version-file: version.txt");
+    repo.write("version.txt", "1.1.0");
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+
+    let output = repo.run(&["bump", "--version", "1.0.0", "--bump", "minor", "--commit", "--create-tag"]);
+    let tags = repo.git(&["tag", "--list"]);
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Nothing to tag"));
+    assert!(tags.stdout.is_empty());
+}
+
+#[test]
+fn test_bump_writes_channel_version_file() {
+    let repo = Scratch::with_config("channel_files", r#"
+current-version-file: stable.txt
+channel-version-files:
+  beta: beta.txt
+"#);
+    repo.write("stable.txt", "1.0.0");
+    repo.write("beta.txt", "2.0.0");
+
+    let output = repo.run(&["bump", "--bump", "minor", "--channel", "beta"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.trim().starts_with("2.1.0"));
+    assert_eq!(repo.read("beta.txt"), stdout.trim());
+    assert_eq!(repo.read("stable.txt"), "1.0.0");
+}
+
+#[test]
+fn test_paths_resolve_against_config_directory() {
+    let repo = Scratch::with_config("config_dir_paths", "current-version-file: version.txt");
+    repo.write("version.txt", "1.0.0");
+    repo.write("other/version.txt", "5.0.0");
+    let run_in_other = |args: &[&str]| {
+        repo.command(env!("CARGO_BIN_EXE_version-it"))
+            .args(["--config", "../.version-it"])
+            .args(args)
+            .current_dir(repo.path("other"))
+            .output()
+            .expect("Failed to run command")
+    };
+
+    let default_base = run_in_other(&["bump", "--bump", "patch"]);
+    let version = repo.read("version.txt");
+    let overridden_base = run_in_other(&["--base-path", ".", "bump", "--bump", "patch"]);
+
+    assert!(default_base.status.success());
+    assert!(overridden_base.status.success());
+    assert_eq!(version, "1.0.1");
+    assert_eq!(repo.read("other/version.txt"), "5.0.1");
+}
+
+#[test]
+fn test_auto_bump_warns_on_tag_divergence() {
+    let repo = Scratch::with_config("tag_divergence", r#"
+current-version-file: version.txt
+change-type-map:
+  - label: feat
+    action: minor
+commit-based-bumping: true
+"#);
+    repo.write("version.txt", "1.2.3");
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+    repo.git(&["tag", "1.2.3"]);
+    repo.write("version.txt", "1.5.0");
+    repo.git(&["commit", "-q", "-am", "feat: drift"]);
+
+    let warned = repo.run(&["auto-bump", "--dry-run"]);
+    let strict = repo.run(&["auto-bump", "--dry-run", "--require-tag-match"]);
+
+    assert!(warned.status.success());
+    assert!(String::from_utf8_lossy(&warned.stdout).starts_with("1.6.0"));
+    assert!(String::from_utf8_lossy(&warned.stderr).contains("latest version tag is 1.2.3"));
+    assert!(!strict.status.success());
+}
+
+#[test]
+fn test_bump_version_file_without_config() {
+    let repo = Scratch::new("version_file_without_config");
+    repo.write("version.txt", "1.2.3\n");
+
+    let dry_run = repo.run(&["--no-search", "bump", "--version-file", "version.txt", "--bump", "patch", "--dry-run"]);
+    let after_dry_run = repo.read("version.txt");
+    let bumped = repo.run(&["--no-search", "bump", "--version-file", "version.txt", "--bump", "patch"]);
+
+    assert!(dry_run.status.success());
+    assert_eq!(after_dry_run, "1.2.3\n");
+    assert!(bumped.status.success());
+    assert_eq!(String::from_utf8_lossy(&bumped.stdout).trim(), "1.2.4");
+    assert_eq!(repo.read("version.txt"), "1.2.4");
+}
+
+#[test]
+fn test_structured_dry_run_is_single_json_document() {
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "--structured-output", "bump", "--version", "1.2.3", "--bump", "patch", "--create-tag", "--dry-run"])
+        .output()
+        .expect("Failed to run command");
+    assert!(output.status.success());
+    let data: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(data["version"], "1.2.4");
+    assert_eq!(data["dry_run"], true);
+    assert_eq!(data["operations"], serde_json::json!(["Create git tag '1.2.4'"]));
+    assert_eq!(data["plan"]["tag"], "1.2.4");
+    assert_eq!(data["plan"]["commit"], false);
+    assert_eq!(data["plan"]["amend"], false);
+    assert_eq!(data["plan"]["version_files"], serde_json::json!([]));
+}
+
+#[test]
+fn test_auto_bump_exit_code_when_no_bump_needed() {
+    let repo = Scratch::with_config("no_bump_exit_code", r#"
+change-type-map:
+  - label: feat
+    action: minor
+commit-based-bumping: true
+"#);
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+    repo.git(&["tag", "1.0.0"]);
+    repo.git(&["commit", "-q", "--allow-empty", "-m", "chore: tidy"]);
+
+    let output = repo.run(&["--structured-output", "auto-bump"]);
+
+    assert_eq!(output.status.code(), Some(3));
+    let data: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(data["message"], "No bump needed");
+}
+
+#[test]
+fn test_tag_message_and_lightweight_tags() {
+    let repo = Scratch::with_config("tag_message", r#"
+current-version-file: version.txt
+changelog-sections:
+  - title: Added
+    labels: ["feat"]
+"#);
+    repo.write("version.txt", "1.0.0");
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+    repo.git(&["tag", "1.0.0"]);
+    repo.git(&["commit", "-q", "--allow-empty", "-m", "feat: shiny thing"]);
+
+    let annotated = repo.run(&["bump", "--bump", "minor", "--commit", "--create-tag", "--tag-message", "Release {{version}}\n\n{{changelog}}"]);
+    let lightweight = repo.run(&["bump", "--bump", "patch", "--commit", "--create-tag", "--lightweight"]);
+    let message = repo.git(&["tag", "-l", "--format=%(contents)", "1.1.0"]);
+    let annotated_type = repo.git(&["cat-file", "-t", "1.1.0"]);
+    let lightweight_type = repo.git(&["cat-file", "-t", "1.1.1"]);
+
+    assert!(annotated.status.success());
+    assert!(lightweight.status.success());
+    let message = String::from_utf8_lossy(&message.stdout);
+    assert!(message.starts_with("Release 1.1.0\n"));
+    assert!(message.contains("### Added\n\n- feat: shiny thing"));
+    assert_eq!(String::from_utf8_lossy(&annotated_type.stdout).trim(), "tag");
+    assert_eq!(String::from_utf8_lossy(&lightweight_type.stdout).trim(), "commit");
+}
+
+#[test]
+fn test_commit_reports_git_lock_after_retries() {
+    let repo = Scratch::with_config("git_lock", "current-version-file: version.txt\ngit-retries: 1");
+    repo.write("version.txt", "1.0.0");
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+    repo.write(".git/index.lock", "");
+
+    let output = repo.run(&["bump", "--bump", "patch", "--commit"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("index.lock"));
+}
+
+#[test]
+fn test_commit_no_verify_skips_hooks() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = Scratch::with_config("no_verify", "current-version-file: version.txt");
+    repo.write("version.txt", "1.0.0");
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+    repo.write(".git/hooks/pre-commit", "#!/bin/sh\nexit 1\n");
+    fs::set_permissions(repo.path(".git/hooks/pre-commit"), fs::Permissions::from_mode(0o755)).unwrap();
+
+    let blocked = repo.run(&["bump", "--bump", "patch", "--commit"]);
+    let skipped = repo.run(&["bump", "--bump", "patch", "--commit", "--no-verify"]);
+    let log = repo.git(&["log", "-1", "--format=%s"]);
+
+    assert!(!blocked.status.success());
+    assert!(skipped.status.success(), "{}", String::from_utf8_lossy(&skipped.stderr));
+    assert_eq!(String::from_utf8_lossy(&log.stdout).trim(), "Bump version to 1.0.2");
+}
+
+#[test]
+fn test_commit_amend_folds_into_last_commit() {
+    let repo = Scratch::with_config("amend", "current-version-file: version.txt");
+    repo.write("version.txt", "1.0.0");
+    let git = |args: &[&str]| String::from_utf8_lossy(&repo.git(args).stdout).trim().to_string();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "init"]);
+    repo.write("notes.txt", "release notes");
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "Prepare release"]);
+    // A stale tag of the new version, which the amend has to move
+    git(&["tag", "1.0.1"]);
+
+    let amended = repo.run(&["bump", "--bump", "patch", "--commit", "--amend", "--create-tag"]);
+    let count = git(&["rev-list", "--count", "HEAD"]);
+    let subject = git(&["log", "-1", "--format=%s"]);
+    let committed_version = git(&["show", "HEAD:version.txt"]);
+    let tagged = git(&["rev-parse", "1.0.1^{commit}"]);
+    let head = git(&["rev-parse", "HEAD"]);
+
+    // A tag of the next version on an older commit is only moved with --force
+    git(&["tag", "1.0.2", "HEAD~1"]);
+    let elsewhere = repo.run(&["bump", "--bump", "patch", "--commit", "--amend", "--create-tag"]);
+    let elsewhere_version = repo.read("version.txt");
+    git(&["tag", "-d", "1.0.2"]);
+
+    git(&["update-ref", "refs/remotes/origin/main", "HEAD"]);
+    let pushed = repo.run(&["bump", "--bump", "patch", "--commit", "--amend"]);
+    let pushed_version = repo.read("version.txt");
+    let forced = repo.run(&["bump", "--bump", "patch", "--commit", "--amend", "--force"]);
+    let forced_count = git(&["rev-list", "--count", "HEAD"]);
+
+    assert!(amended.status.success(), "{}", String::from_utf8_lossy(&amended.stderr));
+    assert_eq!(count, "2");
+    assert_eq!(subject, "Prepare release");
+    assert_eq!(committed_version, "1.0.1");
+    assert_eq!(tagged, head);
+    assert!(!elsewhere.status.success());
+    assert!(String::from_utf8_lossy(&elsewhere.stderr).contains("Tag '1.0.2' already exists on another commit"));
+    assert_eq!(elsewhere_version, "1.0.1");
+    assert!(!pushed.status.success());
+    assert!(String::from_utf8_lossy(&pushed.stderr).contains("Refusing to amend a commit already pushed to origin/main"));
+    assert_eq!(pushed_version, "1.0.1");
+    assert!(forced.status.success(), "{}", String::from_utf8_lossy(&forced.stderr));
+    assert_eq!(forced_count, "2");
+}
+
+#[test]
+fn test_version_resolution_falls_back_to_tag() {
+    let repo = Scratch::with_config("resolution_order", "current-version-file: version.txt");
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+
+    let untagged = repo.run(&["next", "--bump", "patch"]);
+    repo.git(&["tag", "2.3.0"]);
+    let tagged = repo.run(&["next", "--bump", "patch"]);
+
+    // Without a version file, the latest tag comes before first-version
+    assert_eq!(String::from_utf8_lossy(&untagged.stdout).trim(), "1.0.1");
+    assert_eq!(String::from_utf8_lossy(&tagged.stdout).trim(), "2.3.1");
+}
+
+#[test]
+fn test_version_resolution_skips_tags_of_other_schemes() {
+    let repo = Scratch::with_config("resolution_skips_tags", "versioning-scheme: monotonic\nfirst-version: \"41\"");
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+    repo.git(&["tag", "77"]);
+    repo.git(&["tag", "v3.0.0"]);
+
+    let output = repo.run(&["next", "--bump", "patch"]);
+
+    // The latest tag, v3.0.0, is no monotonic version, so first-version is used
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "42");
+}
+
+#[test]
+fn test_version_source_cargo() {
+    let repo = Scratch::with_config("version_source_cargo", "first-version: \"0.1.0\"\nversion-source: cargo");
+    repo.write("Cargo.toml", "[package]\nname = \"app\"\nversion = \"1.4.2\"\n");
+
+    let output = repo.run(&["bump", "--bump", "minor"]);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "1.5.0");
+    assert!(repo.read("Cargo.toml").contains("version = \"1.5.0\""));
+}
+
+#[test]
+fn test_color_output() {
+    // Run the binary directly, as cargo would color its own status lines too
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_version-it"))
+            .arg("--no-search")
+            .args(args)
+            .args(["next", "--version", "not-a-version", "--bump", "patch"])
+            .env("CLICOLOR_FORCE", "1")
+            .env_remove("NO_COLOR")
+            .output()
+            .expect("Failed to run command")
+    };
+    let colored = run(&[]);
+    let plain = run(&["--no-color"]);
+
+    assert!(String::from_utf8_lossy(&colored.stderr).contains("\x1b[31m"));
+    assert!(!String::from_utf8_lossy(&plain.stderr).contains('\x1b'));
+}
+
+#[test]
+fn test_output_formats() {
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "--output-format", "json-pretty", "next", "--version", "1.2.3", "--bump", "patch"])
+        .output()
+        .expect("Failed to run command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let data: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert!(stdout.lines().count() > 1);
+    assert_eq!(data["version"], "1.2.4");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "--output-format", "yaml", "next", "--version", "1.2.3", "--bump", "patch"])
+        .output()
+        .expect("Failed to run command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("version: 1.2.4"), "{}", stdout);
+    assert!(stdout.contains("previous_version: 1.2.3"), "{}", stdout);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "--output-format", "yaml", "next", "--version", "not-a-version", "--bump", "patch"])
+        .output()
+        .expect("Failed to run command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("success: false"));
+}
+
+#[test]
+fn test_config_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+    let run = |args: &[&str], env_config: Option<&str>, config: &str| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_version-it"))
+            .args(args)
+            .args(["next", "--bump", "minor"])
+            .envs(env_config.map(|path| ("VERSION_IT_CONFIG", path)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to run command");
+        // Rejected arguments exit before stdin is read, closing the pipe
+        let _ = child.stdin.take().unwrap().write_all(config.as_bytes());
+        child.wait_with_output().unwrap()
+    };
+    let json = r#"{"run-on-branches": [], "versioning-scheme": "semantic", "first-version": "4.1.0", "calver-enable-branch": false,
+        "changelog-sections": [], "change-substitutions": [], "change-type-map": [], "commit-based-bumping": false, "enable-expensive-metrics": false}"#;
+
+    let output = run(&["--config-stdin"], None, json);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "4.2.0");
+
+    let output = run(&["--config-stdin"], None, "first-version: [");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Error loading config"));
+
+    let output = run(&["--config-stdin", "--config", ".version-it"], None, json);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+
+    // The config piped in wins over one named in the environment
+    let output = run(&["--config-stdin"], Some("no-such-config.yml"), json);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "4.2.0");
+}
+
+#[test]
+fn test_structured_error_codes() {
+    let repo = Scratch::new("structured_error_codes");
+    repo.write("broken.yml", "versioning-scheme: [");
+    let run = |args: &[&str]| {
+        let output = repo.run(&[&["--no-search", "--output-format", "json"], args].concat());
+        assert_eq!(output.status.code(), Some(1));
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    let missing = run(&["auto-bump"]);
+    let invalid = run(&["--config", "broken.yml", "info"]);
+    let failed = run(&["next", "--version", "not-a-version", "--bump", "patch"]);
+    let no_bump = run(&["next", "--version", "1.2.3"]);
+
+    assert_eq!(missing["code"], "CONFIG_NOT_FOUND");
+    assert_eq!(missing["error"], "No config found for auto-bump");
+    assert_eq!(invalid["code"], "CONFIG_INVALID");
+    assert_eq!(failed["code"], "VERSION_PARSE_ERROR");
+    assert_eq!(no_bump["code"], "INVALID_ARGUMENT");
+    assert_eq!(failed["success"], false);
+}
+
+#[test]
+fn test_invalid_bump_and_scheme_rejected_at_parse_time() {
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "next", "--version", "1.2.3", "--bump", "huge"])
+        .output()
+        .expect("Failed to run command");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("possible values: major, minor, patch, auto"), "{}", stderr);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "next", "--version", "1.2.3", "--bump", "patch", "--scheme", "semver"])
+        .output()
+        .expect("Failed to run command");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("semantic-commit"), "{}", stderr);
+}
+
+#[test]
+fn test_diff_headers_between_versions() {
+    let repo = Scratch::with_config("diff_headers", r##"
+first-version: "1.2.3"
+version-headers:
+- path: version.h
+  template: "#define VERSION \"{{version}}\"\n#define SCHEME \"{{scheme}}\"\n"
+"##);
+
+    let output = repo.run(&["diff", "--bump", "minor"]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("--- version.h (1.2.3)\n+++ version.h (1.3.0)\n"), "{}", stdout);
+    assert!(stdout.contains("-#define VERSION \"1.2.3\"\n+#define VERSION \"1.3.0\"\n"), "{}", stdout);
+    assert!(stdout.contains(" #define SCHEME"), "{}", stdout);
+    assert!(!repo.path("version.h").exists());
+}
+
+#[test]
+fn test_numeric_scheme_bump_index() {
+    let next = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_version-it"))
+            .args(["--no-search", "next", "--scheme", "numeric"])
+            .args(args)
+            .output()
+            .expect("Failed to run command")
+    };
+    let indexed = next(&["--version", "1.0.4.2.7", "--bump-index", "3"]);
+    let major = next(&["--version", "3.1", "--bump", "major"]);
+    let missing = next(&["--version", "3.1", "--bump", "patch"]);
+
+    assert_eq!(String::from_utf8_lossy(&indexed.stdout).trim(), "1.0.4.3.0");
+    assert_eq!(String::from_utf8_lossy(&major.stdout).trim(), "4.0");
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("has no component 2"));
+}
+
+#[test]
+fn test_info_lists_capabilities() {
+    let output = Command::new(env!("CARGO_BIN_EXE_version-it"))
+        .args(["--no-search", "--output-format", "json", "info"])
+        .output()
+        .expect("Failed to run command");
+    assert!(output.status.success());
+    let data: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(data["schemes"].as_array().unwrap().contains(&serde_json::json!("semantic-commit")));
+    assert!(data["package_managers"].as_array().unwrap().contains(&serde_json::json!("maven")));
+    assert_eq!(data["header_presets"], serde_json::json!(["rust", "json"]));
+}
+
+#[test]
+fn test_auto_bump_since_overrides_latest_tag() {
+    let repo = Scratch::with_config("auto_bump_since", r#"
+change-type-map:
+  - label: feat
+    action: minor
+commit-based-bumping: true
+"#);
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+    repo.git(&["commit", "-q", "--allow-empty", "-m", "feat: first feature"]);
+    repo.git(&["tag", "1.0.0"]);
+    repo.git(&["commit", "-q", "--allow-empty", "-m", "chore: tidy"]);
+    let root_commit = String::from_utf8(repo.git(&["rev-list", "--max-parents=0", "HEAD"]).stdout).unwrap();
+
+    let from_tag = repo.run(&["auto-bump", "--dry-run"]);
+    let from_root = repo.run(&["--structured-output", "auto-bump", "--dry-run", "--since", root_commit.trim()]);
+    let unknown = repo.run(&["auto-bump", "--dry-run", "--since", "no-such-ref"]);
+
+    assert_eq!(from_tag.status.code(), Some(3));
+    assert!(from_root.status.success());
+    let data: serde_json::Value = serde_json::from_slice(&from_root.stdout).unwrap();
+    assert_eq!(data["version"], "1.1.0");
+    assert_eq!(data["commits"].as_array().unwrap().len(), 2);
+    assert!(String::from_utf8_lossy(&unknown.stderr).contains("Unknown ref 'no-such-ref'"));
+}
+
+#[test]
+fn test_emit_checksum() {
+    let repo = Scratch::with_config("emit_checksum", r##"
+first-version: "0.1.0"
+current-version-file: VERSION
+emit-checksum: true
+version-headers:
+  - path: version.h
+    template: "#define VERSION {{version}}\n"
+"##);
+    repo.write("VERSION", "1.0.0");
+
+    let output = repo.run(&["bump", "--bump", "patch"]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    // sha256 of "1.0.1"
+    assert_eq!(repo.read("VERSION.sha256"), "d51e6ec94058554a84558a5b402ee6ef5fdf5455e35c902feda61cda8752943a  VERSION\n");
+    assert!(repo.read("version.h.sha256").ends_with("  version.h\n"));
+}
+
+#[test]
+fn test_auto_bump_max_bump_caps_major() {
+    let repo = Scratch::with_config("auto_bump_max_bump", r#"
+change-type-map:
+  - label: breaking
+    pattern: "^[0-9a-f]+ feat!"
+    action: major
+commit-based-bumping: true
+"#);
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+    repo.git(&["tag", "1.0.0"]);
+    repo.git(&["commit", "-q", "--allow-empty", "-m", "feat!: drop old API"]);
+
+    let uncapped = repo.run(&["auto-bump", "--dry-run"]);
+    let capped = repo.run(&["auto-bump", "--dry-run", "--max-bump", "minor"]);
+
+    assert!(String::from_utf8_lossy(&uncapped.stdout).starts_with("2.0.0"));
+    assert!(String::from_utf8_lossy(&capped.stdout).starts_with("1.1.0"));
+    assert!(String::from_utf8_lossy(&capped.stderr).contains("capped to minor"));
+}
+
+#[test]
+fn test_auto_bump_check_only_reports_bump_type() {
+    let repo = Scratch::with_config("auto_bump_check", r#"
+current-version-file: version.txt
+change-type-map:
+  - label: feature
+    pattern: "^[0-9a-f]+ feat"
+    action: minor
+commit-based-bumping: true
+"#);
+    repo.write("version.txt", "1.0.0");
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+    repo.git(&["tag", "1.0.0"]);
+
+    let none = repo.run(&["auto-bump", "--check"]);
+    repo.git(&["commit", "-q", "--allow-empty", "-m", "feat: new option"]);
+    let minor = repo.run(&["auto-bump", "--check"]);
+    let structured = repo.run(&["--output-format", "json", "auto-bump", "--check"]);
+    let status = repo.git(&["status", "--porcelain"]);
+
+    assert!(none.status.success());
+    assert_eq!(String::from_utf8_lossy(&none.stdout).trim(), "none");
+    assert_eq!(String::from_utf8_lossy(&minor.stdout).trim(), "minor");
+    let data: serde_json::Value = serde_json::from_slice(&structured.stdout).unwrap();
+    assert_eq!(data["bump_type"], "minor");
+    assert_eq!(repo.read("version.txt"), "1.0.0");
+    assert!(status.stdout.is_empty());
+}
+
+#[test]
+fn test_auto_bump_first_parent_skips_merged_commits() {
+    let repo = Scratch::with_config("auto_bump_first_parent", r#"
+change-type-map:
+  - label: feat
+    action: minor
+  - label: fix
+    action: patch
+commit-based-bumping: true
+"#);
+    repo.git(&["add", "."]);
+    repo.git(&["commit", "-q", "-m", "init"]);
+    repo.git(&["tag", "1.0.0"]);
+    repo.git(&["checkout", "-q", "-b", "topic"]);
+    repo.git(&["commit", "-q", "--allow-empty", "-m", "feat: work in progress"]);
+    repo.git(&["checkout", "-q", "-"]);
+    repo.git(&["merge", "-q", "--no-ff", "topic", "-m", "fix: merge topic"]);
+
+    let all = repo.run(&["auto-bump", "--dry-run"]);
+    let mainline = repo.run(&["auto-bump", "--dry-run", "--first-parent"]);
+
+    assert!(String::from_utf8_lossy(&all.stdout).starts_with("1.1.0"));
+    assert!(String::from_utf8_lossy(&mainline.stdout).starts_with("1.0.1"), "{}", String::from_utf8_lossy(&mainline.stderr));
+}
+
+#[test]
+fn test_retag_normalizes_version_tags() {
+    let repo = Scratch::new("retag");
+    repo.git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+    repo.git(&["tag", "v1.2.3"]);
+    repo.git(&["tag", "-a", "release-1.2.4", "-m", "Release notes"]);
+    repo.git(&["tag", "1.2.4"]);
+    repo.git(&["tag", "nightly"]);
+    repo.git(&["tag", "jdk17"]);
+
+    let planned = repo.run(&["--no-search", "retag"]);
+    let tags_before = String::from_utf8(repo.git(&["tag", "--list"]).stdout).unwrap();
+    let applied = repo.run(&["--no-search", "retag", "--apply", "--delete-old"]);
+    let tags_after = String::from_utf8(repo.git(&["tag", "--list"]).stdout).unwrap();
+
+    assert!(planned.status.success());
+    assert_eq!(String::from_utf8_lossy(&planned.stdout).trim(), "Would rename v1.2.3 -> 1.2.3");
+    assert!(String::from_utf8_lossy(&planned.stderr).contains("skipping release-1.2.4: tag 1.2.4 already exists"));
+    assert_eq!(tags_before.lines().count(), 5);
+    assert!(applied.status.success());
+    assert_eq!(tags_after.lines().collect::<Vec<_>>(), vec!["1.2.3", "1.2.4", "jdk17", "nightly", "release-1.2.4"]);
+}
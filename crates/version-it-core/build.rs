@@ -0,0 +1,16 @@
+use std::process::Command;
+
+fn main() {
+    // Record the compiler that builds this crate so templates report it rather
+    // than whatever rustc happens to be on PATH at runtime.
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=VERSION_IT_RUSTC_VERSION={}", version);
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
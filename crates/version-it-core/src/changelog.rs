@@ -0,0 +1,371 @@
+use std::process::Command;
+use chrono::{DateTime, Utc};
+use handlebars::Handlebars;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use crate::config::ChangelogExporters;
+use crate::error::VersionItError;
+use crate::utils::write_atomic;
+
+/// Marker below which new releases are inserted in prepend mode.
+pub const DEFAULT_CHANGELOG_MARKER: &str = "<!-- version-it -->";
+
+/// Built-in template for the `keep-a-changelog` exporter format.
+pub const KEEP_A_CHANGELOG_TEMPLATE: &str = r#"## [{{version}}] - {{date}}
+{{#each sections}}
+
+### {{title}}
+
+{{#each entries}}
+- {{message}}
+{{/each}}
+{{/each}}
+{{#if repo_url}}
+
+{{#if previous_version}}
+[{{version}}]: {{repo_url}}/compare/{{previous_version}}...{{version}}
+{{else}}
+[{{version}}]: {{repo_url}}/releases/tag/{{version}}
+{{/if}}
+{{/if}}
+"#;
+
+/// Link rules used when `repo-url` is set and no pattern substitution is configured:
+/// commit hashes first, then `#123` issue references.
+pub const DEFAULT_LINK_PATTERNS: &[(&str, &str)] = &[
+    (r"\b([0-9a-f]{7,40})\b", "[$1]({repo-url}/commit/$1)"),
+    (r"(^|[^\[\w])#(\d+)\b", "$1[#$2]({repo-url}/issues/$2)"),
+];
+
+/// How a rendered release is combined with an existing changelog file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangelogMode {
+    /// Insert below the marker, or at the top when there is no marker.
+    #[default]
+    Prepend,
+    /// Add to the end of the file.
+    Append,
+    /// Replace the file.
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangelogEntry {
+    pub hash: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangelogRelease {
+    pub title: String,
+    pub entries: Vec<ChangelogEntry>,
+}
+
+/// The changes going into a release, grouped by `changelog-sections`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Changelog {
+    pub version: String,
+    pub previous_version: Option<String>,
+    pub date: String,
+    pub repo_url: Option<String>,
+    pub sections: Vec<ChangelogRelease>,
+}
+
+impl super::Config {
+    fn changelog_commits(since: Option<&str>) -> Result<Vec<String>, VersionItError> {
+        let range = since.map(|s| format!("{}..HEAD", s)).unwrap_or_else(|| "HEAD".to_string());
+        let output = Command::new("git").args(["log", "--pretty=format:%h %s", &range]).output()?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).lines().map(|l| l.to_string()).collect())
+        } else {
+            Ok(vec![]) // no commits yet
+        }
+    }
+
+    /// Applies token substitutions, then pattern substitutions.
+    ///
+    /// Without any configured pattern, `#123` issue references and commit hashes
+    /// are linked to `repo-url` GitHub style when it is set.
+    fn apply_substitutions(&self, message: &str) -> String {
+        let mut message = self.change_substitutions
+            .iter()
+            .filter(|sub| sub.pattern.is_none() && !sub.token.is_empty())
+            .fold(message.to_string(), |acc, sub| acc.replace(&sub.token, &sub.substitution));
+
+        let repo_url = self.repo_url.as_deref().unwrap_or("").trim_end_matches('/');
+        let mut patterns: Vec<(&str, &str)> = self.change_substitutions
+            .iter()
+            .filter_map(|sub| sub.pattern.as_deref().map(|p| (p, sub.substitution.as_str())))
+            .collect();
+        if patterns.is_empty() && !repo_url.is_empty() {
+            patterns = DEFAULT_LINK_PATTERNS.to_vec();
+        }
+        for (pattern, substitution) in patterns {
+            if let Ok(re) = Regex::new(pattern) {
+                let substitution = substitution.replace("{repo-url}", repo_url);
+                message = re.replace_all(&message, substitution.as_str()).into_owned();
+            }
+        }
+        message
+    }
+
+    /// Groups commit lines (`<hash> <subject>`) into the configured changelog sections.
+    ///
+    /// A commit goes into the first section with a label contained in its subject;
+    /// commits matching no section are left out.
+    pub fn changelog_sections_for(&self, commits: &[String]) -> Vec<ChangelogRelease> {
+        let mut sections: Vec<ChangelogRelease> = self.changelog_sections
+            .iter()
+            .map(|s| ChangelogRelease { title: s.title.clone(), entries: vec![] })
+            .collect();
+        for commit in commits {
+            let (hash, subject) = commit.split_once(' ').unwrap_or(("", commit.as_str()));
+            if let Some(idx) = self.changelog_sections.iter().position(|s| s.labels.iter().any(|l| subject.contains(l.as_str()))) {
+                sections[idx].entries.push(ChangelogEntry {
+                    hash: hash.to_string(),
+                    message: self.apply_substitutions(subject),
+                });
+            }
+        }
+        sections.retain(|s| !s.entries.is_empty());
+        sections
+    }
+
+    /// Collects the changes since the latest version tag for a new release.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The version being released.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the Changelog or an error if git could not be queried.
+    pub fn build_changelog(&self, version: &str) -> Result<Changelog, VersionItError> {
+        let previous_version = self.get_latest_version_tag()?;
+        let commits = Self::changelog_commits(previous_version.as_deref())?;
+        let now: DateTime<Utc> = crate::utils::now();
+        Ok(Changelog {
+            version: version.to_string(),
+            previous_version,
+            date: now.format("%Y-%m-%d").to_string(),
+            repo_url: self.repo_url.as_ref().map(|url| url.trim_end_matches('/').to_string()),
+            sections: self.changelog_sections_for(&commits),
+        })
+    }
+
+    /// Combines a rendered release with the existing changelog content according to the mode.
+    ///
+    /// Returns None when the release is already present, detected by its first
+    /// non-empty line (usually the version heading) appearing in the existing content.
+    pub fn merge_changelog(&self, existing: Option<&str>, rendered: &str) -> Option<String> {
+        self.merge_changelog_with_mode(self.changelog_mode, existing, rendered)
+    }
+
+    /// Like `merge_changelog`, with an exporter's own mode instead of `changelog-mode`.
+    fn merge_changelog_with_mode(&self, mode: ChangelogMode, existing: Option<&str>, rendered: &str) -> Option<String> {
+        let marker = self.changelog_marker.as_deref().unwrap_or(DEFAULT_CHANGELOG_MARKER);
+        let rendered = rendered.trim_end_matches('\n');
+        let existing = match existing {
+            Some(existing) if mode != ChangelogMode::Overwrite => existing,
+            _ if mode == ChangelogMode::Prepend => return Some(format!("{}\n\n{}\n", marker, rendered)),
+            _ => return Some(format!("{}\n", rendered)),
+        };
+
+        if let Some(heading) = rendered.lines().find(|l| !l.trim().is_empty()) {
+            if existing.lines().any(|l| l.trim_end() == heading.trim_end()) {
+                return None;
+            }
+        }
+
+        match mode {
+            ChangelogMode::Append => Some(format!("{}\n\n{}\n", existing.trim_end_matches('\n'), rendered)),
+            _ => match existing.find(marker) {
+                Some(pos) => {
+                    let insert_at = pos + marker.len();
+                    let (head, tail) = existing.split_at(insert_at);
+                    let tail = tail.trim_start_matches('\n');
+                    if tail.is_empty() {
+                        Some(format!("{}\n\n{}\n", head, rendered))
+                    } else {
+                        Some(format!("{}\n\n{}\n\n{}", head, rendered, tail))
+                    }
+                }
+                None => Some(format!("{}\n\n{}", rendered, existing)),
+            },
+        }
+    }
+
+    /// Renders a release like the first changelog exporter, or in the built-in format
+    /// without one.
+    pub fn render_changelog(&self, changelog: &Changelog) -> Result<String, VersionItError> {
+        self.render_changelog_template(self.changelog_exporters.first().and_then(|e| e.template_path.as_deref()), changelog)
+    }
+
+    /// Renders a release with an exporter's template file or built-in format.
+    pub fn render_changelog_for(&self, exporter: &ChangelogExporters, changelog: &Changelog) -> Result<String, VersionItError> {
+        self.render_changelog_template(exporter.template_path.as_deref(), changelog)
+    }
+
+    fn render_changelog_template(&self, template_path: Option<&str>, changelog: &Changelog) -> Result<String, VersionItError> {
+        let template = match template_path {
+            Some(path) => std::fs::read_to_string(self.resolve_path(path))?,
+            None => KEEP_A_CHANGELOG_TEMPLATE.to_string(),
+        };
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(handlebars::no_escape);
+        Ok(handlebars.render_template(&template, changelog)?)
+    }
+
+    /// Renders the changelog for a new release and writes it to every configured exporter.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The version being released.
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or failure.
+    pub fn generate_changelog(&self, version: &str) -> Result<(), VersionItError> {
+        if self.changelog_exporters.is_empty() {
+            return Ok(());
+        }
+        let changelog = self.build_changelog(version)?;
+        for exporter in &self.changelog_exporters {
+            let rendered = self.render_changelog_for(exporter, &changelog)?;
+            let output_path = self.resolve_path(&exporter.output_path);
+            let existing = std::fs::read_to_string(&output_path).ok();
+            let mode = exporter.mode.unwrap_or(self.changelog_mode);
+            if let Some(content) = self.merge_changelog_with_mode(mode, existing.as_deref(), &rendered) {
+                write_atomic(&output_path, content)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Config, ChangelogExporters, ChangeSubstitution};
+
+    fn test_config(mode: &str) -> Config {
+        serde_yaml::from_str(&format!(r#"
+run-on-branches: []
+versioning-scheme: semantic
+first-version: "1.0.0"
+calver-enable-branch: false
+changelog-mode: {}
+changelog-sections:
+  - title: Added
+    labels: ["feat"]
+  - title: Fixed
+    labels: ["fix"]
+change-substitutions:
+  - token: codename-ariel
+    substitution: version.it
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#, mode)).unwrap()
+    }
+
+    #[test]
+    fn test_changelog_sections_for() {
+        let config = test_config("prepend");
+        let commits = vec![
+            "abc1234 feat: add codename-ariel support".to_string(),
+            "def5678 fix: crash on start".to_string(),
+            "0123456 chore: tidy".to_string(),
+        ];
+        let sections = config.changelog_sections_for(&commits);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].title, "Added");
+        assert_eq!(sections[0].entries[0].message, "feat: add version.it support");
+        assert_eq!(sections[1].entries[0].hash, "def5678");
+    }
+
+    fn release(previous_version: Option<&str>) -> Changelog {
+        Changelog {
+            version: "1.2.3".to_string(),
+            previous_version: previous_version.map(|v| v.to_string()),
+            date: "2025-01-01".to_string(),
+            repo_url: Some("https://github.com/owner/repo".to_string()),
+            sections: vec![ChangelogRelease {
+                title: "Fixed".to_string(),
+                entries: vec![ChangelogEntry { hash: "abc1234".to_string(), message: "fix: crash".to_string() }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_render_keep_a_changelog() {
+        let mut config = test_config("prepend");
+        config.changelog_exporters = vec![ChangelogExporters {
+            template_path: None,
+            format: Some("keep-a-changelog".to_string()),
+            output_path: "CHANGELOG.md".to_string(),
+            mode: None,
+        }];
+        let rendered = config.render_changelog(&release(Some("1.2.2"))).unwrap();
+        assert_eq!(rendered, "## [1.2.3] - 2025-01-01\n\n### Fixed\n\n- fix: crash\n\n[1.2.3]: https://github.com/owner/repo/compare/1.2.2...1.2.3\n");
+
+        let rendered = config.render_changelog(&release(None)).unwrap();
+        assert!(rendered.ends_with("[1.2.3]: https://github.com/owner/repo/releases/tag/1.2.3\n"));
+    }
+
+    #[test]
+    fn test_link_issue_and_commit_references() {
+        let mut config = test_config("prepend");
+        assert_eq!(config.apply_substitutions("fix: crash (#123)"), "fix: crash (#123)");
+
+        config.repo_url = Some("https://github.com/owner/repo/".to_string());
+        assert_eq!(config.apply_substitutions("fix: crash (#123)"), "fix: crash ([#123](https://github.com/owner/repo/issues/123))");
+        assert_eq!(config.apply_substitutions("revert abc1234"), "revert [abc1234](https://github.com/owner/repo/commit/abc1234)");
+        assert_eq!(config.apply_substitutions("docs: update readme"), "docs: update readme");
+    }
+
+    #[test]
+    fn test_link_pattern_override() {
+        let mut config = test_config("prepend");
+        config.repo_url = Some("https://gitlab.com/owner/repo".to_string());
+        config.change_substitutions.push(ChangeSubstitution {
+            token: String::new(),
+            pattern: Some(r"#(\d+)".to_string()),
+            substitution: "[#$1]({repo-url}/-/issues/$1)".to_string(),
+        });
+        assert_eq!(config.apply_substitutions("fix: crash (#7) in abc1234"), "fix: crash ([#7](https://gitlab.com/owner/repo/-/issues/7)) in abc1234");
+    }
+
+    #[test]
+    fn test_merge_changelog_prepend_below_marker() {
+        let config = test_config("prepend");
+        let existing = "# Changelog\n<!-- version-it -->\n\n## 1.0.0\n- first\n";
+        let merged = config.merge_changelog(Some(existing), "## 1.1.0\n- second\n").unwrap();
+        assert_eq!(merged, "# Changelog\n<!-- version-it -->\n\n## 1.1.0\n- second\n\n## 1.0.0\n- first\n");
+    }
+
+    #[test]
+    fn test_merge_changelog_new_file_gets_marker() {
+        let config = test_config("prepend");
+        let merged = config.merge_changelog(None, "## 1.0.0\n").unwrap();
+        assert_eq!(merged, "<!-- version-it -->\n\n## 1.0.0\n");
+    }
+
+    #[test]
+    fn test_merge_changelog_append_and_overwrite() {
+        let existing = "## 1.0.0\n- first\n";
+        let merged = test_config("append").merge_changelog(Some(existing), "## 1.1.0\n").unwrap();
+        assert_eq!(merged, "## 1.0.0\n- first\n\n## 1.1.0\n");
+        let merged = test_config("overwrite").merge_changelog(Some(existing), "## 1.1.0\n").unwrap();
+        assert_eq!(merged, "## 1.1.0\n");
+    }
+
+    #[test]
+    fn test_merge_changelog_skips_existing_version() {
+        let config = test_config("prepend");
+        let existing = "<!-- version-it -->\n\n## 1.1.0\n- second\n";
+        assert!(config.merge_changelog(Some(existing), "## 1.1.0\n- second\n").is_none());
+    }
+}
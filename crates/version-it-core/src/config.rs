@@ -1,28 +1,103 @@
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use regex;
+use crate::error::VersionItError;
+use crate::changelog::ChangelogMode;
+use crate::utils::is_glob;
+use crate::version::{CalverFormat, PatternVersion, Scheme, VersionInfo};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Suffix of snapshot versions when the config doesn't set `snapshot-suffix`.
+pub const DEFAULT_SNAPSHOT_SUFFIX: &str = "-SNAPSHOT";
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChangelogExporters {
     #[serde(rename = "template-path")]
-    pub template_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_path: Option<String>,
+    /// Built-in format used instead of a template, currently only `keep-a-changelog`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
     #[serde(rename = "output-path")]
     pub output_path: String,
+    /// How this exporter combines releases with its file, overriding `changelog-mode`,
+    /// e.g. `overwrite` for a JSON feed next to a prepended markdown changelog.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<ChangelogMode>,
+}
+
+/// YAML form of `changelog-exporters`: a single exporter or a list of them.
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum ChangelogExportersRepr {
+    One(ChangelogExporters),
+    Many(Vec<ChangelogExporters>),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn deserialize_changelog_exporters<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<ChangelogExporters>, D::Error> {
+    Ok(match ChangelogExportersRepr::deserialize(deserializer)? {
+        ChangelogExportersRepr::One(exporter) => vec![exporter],
+        ChangelogExportersRepr::Many(exporters) => exporters,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChangelogSection {
     pub title: String,
     pub labels: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChangeSubstitution {
+    #[serde(default)]
     pub token: String,
+    /// Regex replaced instead of `token`; the substitution may use `$1` style
+    /// groups and `{repo-url}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
     pub substitution: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A place the current version can be resolved from (`version-resolution-order`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum VersionResolutionStep {
+    /// The version source: the version file, `Cargo.toml` or a package file.
+    Source,
+    /// The latest version tag.
+    Tag,
+    /// `first-version`, which always resolves.
+    FirstVersion,
+}
+
+impl VersionResolutionStep {
+    /// The name used in the config, e.g. `first-version`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VersionResolutionStep::Source => "source",
+            VersionResolutionStep::Tag => "tag",
+            VersionResolutionStep::FirstVersion => "first-version",
+        }
+    }
+}
+
+/// Where the current version is looked for when `version-resolution-order` is not set.
+pub const DEFAULT_VERSION_RESOLUTION_ORDER: [VersionResolutionStep; 3] =
+    [VersionResolutionStep::Source, VersionResolutionStep::Tag, VersionResolutionStep::FirstVersion];
+
+/// Line endings written to generated headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ChangeAction {
     Null,
@@ -31,45 +106,220 @@ pub enum ChangeAction {
     Major,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChangeTypeMap {
     pub label: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pattern: Option<String>,
     pub action: ChangeAction,
+    /// Match the label or pattern regardless of case, e.g. `feat` also matches `Feat:`.
+    #[serde(rename = "case-insensitive", default)]
+    pub case_insensitive: bool,
+    /// Only match at the start of the commit subject instead of anywhere in it.
+    #[serde(default)]
+    pub anchored: bool,
+}
+
+impl ChangeTypeMap {
+    /// Compiles `pattern` with the `case-insensitive` and `anchored` options applied.
+    pub fn regex(&self) -> Option<Result<regex::Regex, regex::Error>> {
+        let pattern = self.pattern.as_ref()?;
+        let flags = if self.case_insensitive { "(?i)" } else { "" };
+        let anchor = if self.anchored { "^" } else { "" };
+        Some(regex::Regex::new(&format!("{}{}(?:{})", flags, anchor, pattern)))
+    }
+
+    /// Whether a commit from `git log --oneline` matches this entry. Anchored entries
+    /// match against the subject after the abbreviated hash.
+    fn matches(&self, commit: &str) -> bool {
+        let text = if self.anchored { commit_subject(commit) } else { commit };
+        if let Some(Ok(re)) = self.regex() {
+            return re.is_match(text);
+        }
+        // Without a pattern, or with an invalid one, fall back to the label
+        match (self.case_insensitive, self.anchored) {
+            (false, false) => text.contains(&self.label),
+            (false, true) => text.starts_with(&self.label),
+            (true, false) => text.to_lowercase().contains(&self.label.to_lowercase()),
+            (true, true) => text.to_lowercase().starts_with(&self.label.to_lowercase()),
+        }
+    }
+}
+
+/// Strips the abbreviated hash `git log --oneline` puts in front of the subject.
+fn commit_subject(commit: &str) -> &str {
+    match commit.split_once(' ') {
+        Some((hash, subject)) if hash.len() >= 7 && hash.chars().all(|c| c.is_ascii_hexdigit()) => subject,
+        _ => commit,
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How a commit was classified by `change-type-map`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommitClassification {
+    /// The commit as `<short hash> <subject>`.
+    pub commit: String,
+    /// Label of the matching entry; set without an action when only null entries matched.
+    pub matched_label: Option<String>,
+    /// The resulting bump, if any.
+    pub action: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct VersionHeader {
     pub path: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub template: Option<String>,
     #[serde(rename = "template-path", skip_serializing_if = "Option::is_none")]
     pub template_path: Option<String>,
+    /// Built-in template to render instead of `template`/`template-path`: `rust`, or
+    /// `json` for a JSON version manifest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct PackageFile {
     pub path: String,
     pub manager: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub field: Option<String>,
+    /// Regex with a capture group around the version, used by the `regex` manager.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Update this dependency's version requirement instead of the package version
+    /// (npm/yarn/pnpm and cargo managers).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependency: Option<String>,
+    /// Handlebars template for the version written to this file, e.g. `{{version}}-1`
+    /// or `{{major}},{{minor}},{{patch}},0`. Has `version`, `major`, `minor`, `patch`,
+    /// `pre` and `build`.
+    #[serde(rename = "version-format")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_format: Option<String>,
+}
+
+/// Where the current version is read from, and written to on bump.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(from = "VersionSourceRepr", into = "VersionSourceRepr")]
+pub enum VersionSource {
+    /// `current-version-file` (or `channel-version-files`), containing only the version.
+    #[default]
+    File,
+    /// `package.version` in `Cargo.toml`, or `workspace.package.version` when the
+    /// package inherits it.
+    Cargo,
+    /// A field of a structured file, described like a `package-files` entry.
+    Package(PackageFile),
+}
+
+/// YAML form of `version-source`: `file`, `cargo` or `package: { path, manager, ... }`.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum VersionSourceRepr {
+    Named(NamedVersionSource),
+    Package { package: PackageFile },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum NamedVersionSource {
+    File,
+    Cargo,
+}
+
+impl From<VersionSourceRepr> for VersionSource {
+    fn from(repr: VersionSourceRepr) -> Self {
+        match repr {
+            VersionSourceRepr::Named(NamedVersionSource::File) => VersionSource::File,
+            VersionSourceRepr::Named(NamedVersionSource::Cargo) => VersionSource::Cargo,
+            VersionSourceRepr::Package { package } => VersionSource::Package(package),
+        }
+    }
+}
+
+impl From<VersionSource> for VersionSourceRepr {
+    fn from(source: VersionSource) -> Self {
+        match source {
+            VersionSource::File => VersionSourceRepr::Named(NamedVersionSource::File),
+            VersionSource::Cargo => VersionSourceRepr::Named(NamedVersionSource::Cargo),
+            VersionSource::Package(package) => VersionSourceRepr::Package { package },
+        }
+    }
+}
+
+impl VersionSource {
+    /// Returns the structured file holding the version, or None for `file`.
+    pub fn package_file(&self) -> Option<PackageFile> {
+        match self {
+            VersionSource::File => None,
+            VersionSource::Cargo => Some(PackageFile {
+                path: "Cargo.toml".to_string(),
+                manager: "cargo".to_string(),
+                field: None,
+                pattern: None,
+                dependency: None,
+                version_format: None,
+            }),
+            VersionSource::Package(package_file) => Some(package_file.clone()),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     #[serde(rename = "run-on-branches")]
     pub run_on_branches: Vec<String>,
     #[serde(rename = "versioning-scheme")]
-    pub versioning_scheme: String,
+    #[schemars(schema_with = "versioning_scheme_schema")]
+    pub versioning_scheme: Scheme,
+    /// Layout of calver versions: `YY.MM.DD` (default) or `YYYY.WW` (ISO week).
+    #[serde(rename = "calver-format", default)]
+    pub calver_format: CalverFormat,
+    /// Template of the pattern scheme, e.g. `{major}.{minor}.{date:%Y%m%d}.{commit}`.
+    #[serde(rename = "version-pattern")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_pattern: Option<String>,
     #[serde(rename = "first-version")]
     pub first_version: String,
+    /// Where the current version is looked for, in order (default: `source`, `tag`,
+    /// `first-version`).
+    #[serde(rename = "version-resolution-order", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub version_resolution_order: Vec<VersionResolutionStep>,
     #[serde(rename = "current-version-file")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub current_version_file: Option<String>,
-    #[serde(rename = "changelog-exporters")]
+    /// Per-channel overrides of `current-version-file`, e.g. `beta: version-beta.txt`.
+    #[serde(rename = "channel-version-files")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_version_files: Option<BTreeMap<String, String>>,
+    /// Write a `<file>.sha256` next to the version file and generated headers.
+    #[serde(rename = "emit-checksum", default)]
+    pub emit_checksum: bool,
+    /// Line endings of generated headers, `lf` or `crlf` (default: as in the template).
+    #[serde(rename = "line-ending")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_ending: Option<LineEnding>,
+    #[serde(rename = "version-source", default)]
+    #[schemars(with = "VersionSourceRepr")]
+    pub version_source: VersionSource,
+    /// Changelog files rendered on every bump; a single exporter or a list.
+    #[serde(rename = "changelog-exporters", default, deserialize_with = "deserialize_changelog_exporters")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schemars(with = "ChangelogExportersRepr")]
+    pub changelog_exporters: Vec<ChangelogExporters>,
+    #[serde(rename = "changelog-mode", default)]
+    pub changelog_mode: ChangelogMode,
+    /// Line below which prepend mode inserts new releases (default `<!-- version-it -->`).
+    #[serde(rename = "changelog-marker")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changelog_marker: Option<String>,
+    /// Web URL of the repository, used for links in changelogs.
+    #[serde(rename = "repo-url")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub changelog_exporters: Option<ChangelogExporters>,
+    pub repo_url: Option<String>,
+    /// Append the slugified current branch to calver versions, e.g. `25.10.01-main`.
     #[serde(rename = "calver-enable-branch")]
     pub calver_enable_branch: bool,
     #[serde(rename = "changelog-sections")]
@@ -87,17 +337,134 @@ pub struct Config {
     #[serde(rename = "channel")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub channel: Option<String>,
+    /// Separator between the version and the channel suffix (default `-`).
+    #[serde(rename = "channel-separator")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_separator: Option<String>,
     #[serde(rename = "commit-based-bumping")]
     pub commit_based_bumping: bool,
+    /// Highest bump commit-based bumping may pick (`major`, `minor` or `patch`); larger
+    /// bumps are lowered to it with a warning.
+    #[serde(rename = "max-auto-bump")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_auto_bump: Option<String>,
+    /// Suffix of snapshot (development) versions written by `--snapshot`, as a semver
+    /// prerelease starting with `-` (default `-SNAPSHOT`).
+    #[serde(rename = "snapshot-suffix")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_suffix: Option<String>,
+    /// Only analyze the mainline (first-parent) commits, so the commits of merged
+    /// branches don't count towards the bump.
+    #[serde(rename = "commit-analysis-first-parent", default)]
+    pub commit_analysis_first_parent: bool,
     #[serde(rename = "enable-expensive-metrics")]
     pub enable_expensive_metrics: bool,
     #[serde(rename = "structured-output", default)]
     pub structured_output: bool,
+    #[serde(rename = "monotonic-step")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monotonic_step: Option<u64>,
+    #[serde(rename = "enforce-branches", default)]
+    pub enforce_branches: bool,
+    /// Skip semver prerelease tags (e.g. `1.3.0-beta.1`) when looking for the latest
+    /// version tag, so stable releases are based on the last stable tag.
+    #[serde(rename = "ignore-prerelease-tags", default)]
+    pub ignore_prerelease_tags: bool,
+    /// Accept tags with a prefix before the version, such as `v1.2.0` or
+    /// `release/1.2.0`, and find the latest by parsing and comparing the versions
+    /// instead of trusting git's version sort.
+    #[serde(rename = "mixed-tag-prefixes", default)]
+    pub mixed_tag_prefixes: bool,
+    /// Retries for git commit and tag commands that fail on a lock held by another git
+    /// process (default 2).
+    #[serde(rename = "git-retries")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_retries: Option<u32>,
+    /// Directory that relative paths in the config are resolved against.
+    #[serde(skip)]
+    pub base_path: Option<PathBuf>,
+}
+
+/// Expands `${VAR}` and `${VAR:-default}` in every string value (not key) of a YAML
+/// document. Numbers and booleans are left alone, so expansion never changes a type.
+fn expand_env_in_value(value: &mut serde_yaml::Value, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), VersionItError> {
+    match value {
+        serde_yaml::Value::String(s) => *s = expand_env(s, lookup)?,
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                expand_env_in_value(item, lookup)?;
+            }
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            for (_, item) in mapping.iter_mut() {
+                expand_env_in_value(item, lookup)?;
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => expand_env_in_value(&mut tagged.value, lookup)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Expands environment variable references in a string. A variable that is unset
+/// or empty takes the default; without a default it is an error.
+fn expand_env(input: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, VersionItError> {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start..];
+        if let Some(escaped) = after.strip_prefix("$${") {
+            result.push_str("${");
+            rest = escaped;
+        } else if let Some(reference) = after.strip_prefix("${") {
+            let end = reference.find('}')
+                .ok_or_else(|| VersionItError::Config(format!("Unclosed '${{' in config value '{}'", input)))?;
+            let (name, default) = match reference[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&reference[..end], None),
+            };
+            match (lookup(name).filter(|v| !v.is_empty()), default) {
+                (Some(value), _) => result.push_str(&value),
+                (None, Some(default)) => result.push_str(default),
+                (None, None) => return Err(VersionItError::Config(format!("Environment variable '{}' used in the config is not set", name))),
+            }
+            rest = &reference[end + 1..];
+        } else {
+            result.push('$');
+            rest = &after[1..];
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn versioning_scheme_schema(_gen: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        enum_values: Some(Scheme::ALL.iter().map(|s| serde_json::json!(s.as_str())).collect()),
+        ..Default::default()
+    }
+    .into()
 }
 
 impl Config {
+    /// Returns a JSON Schema describing the config file format, for editor validation.
+    pub fn json_schema() -> serde_json::Value {
+        serde_json::to_value(schemars::schema_for!(Config)).expect("JSON Schema serializes to JSON")
+    }
+
     /// Loads configuration from a YAML file.
     ///
+    /// Relative paths in the config (version file, headers, package files, changelog)
+    /// resolve against `base_path`, which is set here to the directory containing the
+    /// config file, so the result does not depend on the working directory. Callers may
+    /// override `base_path` afterwards (the CLI does for `--base-path`). A config in the
+    /// working directory itself leaves `base_path` unset, meaning the working directory.
+    ///
+    /// `${VAR}` and `${VAR:-default}` in string values are replaced from the environment
+    /// before the config is parsed; `$${` is a literal `${`.
+    ///
     /// # Arguments
     ///
     /// * `path` - The path to the YAML configuration file.
@@ -105,27 +472,317 @@ impl Config {
     /// # Returns
     ///
     /// A Result containing the Config or an error if loading/parsing fails.
-    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn load_from_file(path: &str) -> Result<Self, VersionItError> {
+        let config = Self::from_value(Self::read_yaml(path)?, false)?;
+        config.finish_loading(path)
+    }
+
+    /// Loads configuration from a YAML file, rejecting unknown keys.
+    ///
+    /// Like `load_from_file`, but a typo such as `versioning_scheme` for
+    /// `versioning-scheme` is an error instead of being silently ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the YAML configuration file.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the Config or an error listing the unknown keys.
+    pub fn load_from_file_strict(path: &str) -> Result<Self, VersionItError> {
+        let config = Self::from_value(Self::read_yaml(path)?, true)?;
+        config.finish_loading(path)
+    }
+
+    /// Loads configuration from a string, such as a config piped to stdin.
+    ///
+    /// The contents are parsed as JSON when they start with `{`, as YAML otherwise.
+    /// Environment variables are expanded as in `load_from_file`, and relative paths
+    /// resolve against the working directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The YAML or JSON configuration.
+    /// * `strict` - Whether unknown keys are an error, as in `load_from_file_strict`.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the Config or an error if parsing fails.
+    pub fn load_from_str(contents: &str, strict: bool) -> Result<Self, VersionItError> {
+        let mut value: serde_yaml::Value = if contents.trim_start().starts_with('{') {
+            let json: serde_json::Value = serde_json::from_str(contents)
+                .map_err(|e| VersionItError::Config(format!("Invalid JSON config: {}", e)))?;
+            serde_yaml::to_value(json)?
+        } else {
+            serde_yaml::from_str(contents)?
+        };
+        expand_env_in_value(&mut value, &|name| std::env::var(name).ok())?;
+        let config = Self::from_value(value, strict)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reads a YAML file and expands environment variables in its string values.
+    fn read_yaml(path: &str) -> Result<serde_yaml::Value, VersionItError> {
         let contents = std::fs::read_to_string(path)?;
-        let config: Config = serde_yaml::from_str(&contents)?;
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&contents)?;
+        expand_env_in_value(&mut value, &|name| std::env::var(name).ok())?;
+        Ok(value)
+    }
+
+    /// Deserializes a parsed config, listing unknown keys as an error when `strict`.
+    fn from_value(value: serde_yaml::Value, strict: bool) -> Result<Self, VersionItError> {
+        if !strict {
+            return Ok(serde_yaml::from_value(value)?);
+        }
+        let mut unknown = Vec::new();
+        let config: Config = serde_ignored::deserialize(value, |key| unknown.push(key.to_string()))?;
+        if !unknown.is_empty() {
+            return Err(VersionItError::Config(format!("Unknown config keys: {}", unknown.join(", "))));
+        }
         Ok(config)
     }
 
-    pub fn get_current_version(&self) -> Result<String, Box<dyn std::error::Error>> {
-        if let Some(ref file) = self.current_version_file {
-            let version = std::fs::read_to_string(file)?;
+    fn finish_loading(mut self, path: &str) -> Result<Self, VersionItError> {
+        self.validate()?;
+        self.base_path = Path::new(path)
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(|dir| dir.to_path_buf());
+        Ok(self)
+    }
+
+    /// Checks settings that cannot be expressed through deserialization alone.
+    ///
+    /// # Returns
+    ///
+    /// An error describing the first invalid setting found.
+    pub fn validate(&self) -> Result<(), VersionItError> {
+        for exporter in &self.changelog_exporters {
+            match (&exporter.template_path, exporter.format.as_deref()) {
+                (Some(_), Some(_)) => return Err(VersionItError::Config("changelog-exporters takes either template-path or format, not both".to_string())),
+                (None, None) => return Err(VersionItError::Config("changelog-exporters needs a template-path or a format".to_string())),
+                (None, Some(format)) if format != "keep-a-changelog" => {
+                    return Err(VersionItError::Config(format!("Unknown changelog format '{}'. Use keep-a-changelog.", format)));
+                }
+                _ => {}
+            }
+        }
+        for header in self.version_headers.iter().flatten() {
+            let sources = [header.template.is_some(), header.template_path.is_some(), header.preset.is_some()];
+            if sources.iter().filter(|s| **s).count() != 1 {
+                return Err(VersionItError::Config(format!("Version header '{}' needs exactly one of template, template-path or preset", header.path)));
+            }
+            if let Some(ref preset) = header.preset {
+                if !crate::templates::HEADER_PRESETS.contains(&preset.as_str()) {
+                    return Err(VersionItError::Config(format!("Unknown header preset '{}'. Use one of: {}", preset, crate::templates::HEADER_PRESETS.join(", "))));
+                }
+            }
+        }
+        for sub in &self.change_substitutions {
+            if let Some(ref pattern) = sub.pattern {
+                regex::Regex::new(pattern)
+                    .map_err(|e| VersionItError::Config(format!("Invalid change-substitutions pattern '{}': {}", pattern, e)))?;
+            }
+        }
+        if let Some(ref max) = self.max_auto_bump {
+            if !["major", "minor", "patch"].contains(&max.as_str()) {
+                return Err(VersionItError::Config(format!("Invalid max-auto-bump '{}'. Use major, minor or patch.", max)));
+            }
+        }
+        for (i, step) in self.version_resolution_order.iter().enumerate() {
+            if self.version_resolution_order[..i].contains(step) {
+                return Err(VersionItError::Config(format!("version-resolution-order lists {} twice", step.as_str())));
+            }
+        }
+        if let Some(ref suffix) = self.snapshot_suffix {
+            if !suffix.strip_prefix('-').is_some_and(|pre| !pre.is_empty() && semver::Prerelease::new(pre).is_ok()) {
+                return Err(VersionItError::Config(format!("Invalid snapshot-suffix '{}'. Use a dash and a semver prerelease, e.g. -SNAPSHOT.", suffix)));
+            }
+        }
+        if let Some(ref template) = self.version_pattern {
+            PatternVersion::validate_template(template)
+                .map_err(|e| VersionItError::Config(format!("Invalid version-pattern: {}", e)))?;
+        }
+        if let VersionSource::Package(ref source) = self.version_source {
+            if source.dependency.is_some() {
+                return Err(VersionItError::Config(format!("Version source '{}' cannot use dependency", source.path)));
+            }
+            if is_glob(&source.path) {
+                return Err(VersionItError::Config(format!("Version source '{}' must be a single file, not a glob", source.path)));
+            }
+            if source.manager == "regex" && source.pattern.is_none() {
+                return Err(VersionItError::Config(format!("Version source '{}' uses the regex manager but has no pattern", source.path)));
+            }
+        }
+        if let Some(ref package_files) = self.package_files {
+            for package_file in package_files {
+                if package_file.dependency.is_some() && !["npm", "yarn", "pnpm", "cargo"].contains(&package_file.manager.as_str()) {
+                    return Err(VersionItError::Config(format!("Package file '{}': dependency is only supported by the npm, yarn, pnpm and cargo managers", package_file.path)));
+                }
+                if package_file.manager == "regex" {
+                    let pattern = package_file.pattern.as_ref()
+                        .ok_or_else(|| VersionItError::Config(format!("Package file '{}' uses the regex manager but has no pattern", package_file.path)))?;
+                    let re = regex::Regex::new(pattern)
+                        .map_err(|e| VersionItError::Config(format!("Invalid pattern for package file '{}': {}", package_file.path, e)))?;
+                    if re.captures_len() < 2 {
+                        return Err(VersionItError::Config(format!("Pattern for package file '{}' must contain a capture group for the version", package_file.path)));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Searches for a config file, starting in the current directory and walking
+    /// up toward the filesystem root.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The relative path of the config file to look for.
+    ///
+    /// # Returns
+    ///
+    /// The path of the first matching file, or None if no directory contains it.
+    pub fn find_config_file(name: &str) -> Option<PathBuf> {
+        let mut dir = std::env::current_dir().ok()?;
+        loop {
+            let candidate = dir.join(name);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+            if !dir.pop() {
+                return None;
+            }
+        }
+    }
+
+    /// Resolves a path from the config against `base_path`.
+    ///
+    /// Absolute paths, and all paths when no base path is set, are returned unchanged.
+    pub fn resolve_path(&self, path: &str) -> PathBuf {
+        match self.base_path {
+            Some(ref base) if Path::new(path).is_relative() => base.join(path),
+            _ => PathBuf::from(path),
+        }
+    }
+
+    /// Returns the version file for a channel, falling back to `current-version-file`.
+    pub fn version_file_for(&self, channel: Option<&str>) -> Option<&str> {
+        channel
+            .and_then(|c| self.channel_version_files.as_ref().and_then(|files| files.get(c)))
+            .or(self.current_version_file.as_ref())
+            .map(|f| f.as_str())
+    }
+
+    pub fn get_current_version(&self) -> Result<String, VersionItError> {
+        self.get_current_version_for_channel(self.channel.as_deref())
+    }
+
+    /// Reads the current version from the version source, or returns `first-version`
+    /// when the channel has no version file.
+    pub fn get_current_version_for_channel(&self, channel: Option<&str>) -> Result<String, VersionItError> {
+        if let Some(source) = self.version_source.package_file() {
+            self.read_source_version(&source)
+        } else if let Some(file) = self.version_file_for(channel) {
+            let version = std::fs::read_to_string(self.resolve_path(file))?;
             Ok(version.trim().to_string())
         } else {
             Ok(self.first_version.clone())
         }
     }
 
+    /// Resolves the current version by trying each step of `version-resolution-order`
+    /// in turn, by default the version source, then the latest version tag, then
+    /// `first-version`.
+    ///
+    /// The version source is skipped when there is no version file for the channel or
+    /// it can't be read, and the tag step when there is no version tag or it doesn't parse
+    /// under the versioning scheme.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel whose version file to read.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the current version, or an error when no step resolves it.
+    pub fn resolve_current_version(&self, channel: Option<&str>) -> Result<String, VersionItError> {
+        let order = if self.version_resolution_order.is_empty() {
+            &DEFAULT_VERSION_RESOLUTION_ORDER[..]
+        } else {
+            &self.version_resolution_order[..]
+        };
+        for step in order {
+            match step {
+                VersionResolutionStep::Source => {
+                    if self.version_source.package_file().is_none() && self.version_file_for(channel).is_none() {
+                        continue;
+                    }
+                    match self.get_current_version_for_channel(channel) {
+                        Ok(version) => return Ok(version),
+                        Err(e) => log::debug!("No current version from the version source: {}", e),
+                    }
+                }
+                VersionResolutionStep::Tag => match self.get_latest_version_tag() {
+                    // Outside semantic and calver any tag counts as a version tag, so
+                    // only take it when it parses under the versioning scheme
+                    Ok(Some(tag)) => match VersionInfo::new(self.tag_version_str(&tag), self.versioning_scheme, None) {
+                        Ok(_) => return Ok(self.tag_version_str(&tag).to_string()),
+                        Err(e) => log::debug!("No current version from the latest tag {}: {}", tag, e),
+                    },
+                    Ok(None) => {}
+                    Err(e) => log::debug!("No current version from the latest tag: {}", e),
+                },
+                VersionResolutionStep::FirstVersion => return Ok(self.first_version.clone()),
+            }
+        }
+        Err(VersionItError::Config("No current version found by version-resolution-order".to_string()))
+    }
+
+    /// Compares the version file against the latest version tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel whose version file to read.
+    ///
+    /// # Returns
+    ///
+    /// Some((file_version, tag)) when both exist and differ, None otherwise.
+    pub fn version_tag_divergence(&self, channel: Option<&str>) -> Result<Option<(String, String)>, VersionItError> {
+        if self.version_source == VersionSource::File && self.version_file_for(channel).is_none() {
+            return Ok(None);
+        }
+        let file_version = match self.get_current_version_for_channel(channel) {
+            Ok(version) => version,
+            Err(_) => return Ok(None),
+        };
+        match self.get_latest_version_tag()? {
+            Some(tag) if self.tag_version_str(&tag) != file_version => Ok(Some((file_version, tag))),
+            _ => Ok(None),
+        }
+    }
+
     /// Analyzes recent commits to determine if a version bump is needed.
     ///
     /// # Returns
     ///
     /// A Result containing Some(bump_type) if a bump is needed, None otherwise, or an error.
-    pub fn analyze_commits_for_bump(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    pub fn analyze_commits_for_bump(&self) -> Result<Option<String>, VersionItError> {
+        self.analyze_commits_for_bump_since(None)
+    }
+
+    /// Like `analyze_commits_for_bump`, but analyzes the commits after `since` when given
+    /// instead of those after the latest version tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `since` - A git ref overriding the latest version tag, e.g. the root commit.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing Some(bump_type) if a bump is needed, None otherwise, or an error.
+    pub fn analyze_commits_for_bump_since(&self, since: Option<&str>) -> Result<Option<String>, VersionItError> {
         // Check if commit-based bumping is enabled
         if !self.commit_based_bumping {
             return Ok(None);
@@ -137,56 +794,159 @@ impl Config {
             return Ok(None);
         }
 
-        // Find latest version tag
-        let latest_tag = self.get_latest_version_tag()?;
-        let since = latest_tag.as_deref().unwrap_or("HEAD~1");
-
-        // Get commits since last tag
-        let commits = self.get_commits_since(since)?;
-
         // Analyze commits for bump type
         let mut bump_type: Option<String> = None;
-        for commit in commits {
+        for commit in self.commits_for_bump(since)? {
             if let Some(bt) = self.determine_bump_from_commit(&commit) {
                 bump_type = self.higher_bump(bump_type.as_deref(), Some(&bt));
             }
         }
 
-        Ok(bump_type)
+        Ok(bump_type.map(|bump| self.cap_auto_bump(bump)))
     }
 
-    fn get_current_branch(&self) -> Result<String, Box<dyn std::error::Error>> {
+    /// Lowers a bump to `max-auto-bump`, if it exceeds it.
+    fn cap_auto_bump(&self, bump: String) -> String {
+        match self.max_auto_bump.as_deref() {
+            Some(max) if self.higher_bump(Some(max), Some(&bump)).as_deref() != Some(max) => {
+                log::warn!("Commits call for a {} bump, capped to {} by max-auto-bump", bump, max);
+                max.to_string()
+            }
+            _ => bump,
+        }
+    }
+
+    /// Explains how each commit since the last version tag is classified by `change-type-map`.
+    ///
+    /// Unlike `analyze_commits_for_bump`, this ignores `commit-based-bumping` and the
+    /// branch list, so misconfigured maps can be debugged anywhere.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing one classification per commit, newest first, or an error.
+    pub fn explain_commits_for_bump(&self) -> Result<Vec<CommitClassification>, VersionItError> {
+        self.explain_commits_for_bump_since(None)
+    }
+
+    /// Like `explain_commits_for_bump`, but for the commits after `since` when given.
+    pub fn explain_commits_for_bump_since(&self, since: Option<&str>) -> Result<Vec<CommitClassification>, VersionItError> {
+        Ok(self.commits_for_bump(since)?.iter().map(|commit| self.classify_commit(commit)).collect())
+    }
+
+    fn commits_for_bump(&self, since: Option<&str>) -> Result<Vec<String>, VersionItError> {
+        // An explicit ref takes precedence over the latest version tag
+        if let Some(since) = since {
+            let verified = Command::new("git").args(["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", since)]).output()?;
+            if !verified.status.success() {
+                return Err(VersionItError::Git(format!("Unknown ref '{}'", since)));
+            }
+            return self.get_commits_since(since);
+        }
+        let latest_tag = self.get_latest_version_tag()?;
+        let since = latest_tag.as_deref().unwrap_or("HEAD~1");
+
+        // Get commits since last tag
+        self.get_commits_since(since)
+    }
+
+    pub fn get_current_branch(&self) -> Result<String, VersionItError> {
         let output = Command::new("git").args(["rev-parse", "--abbrev-ref", "HEAD"]).output()?;
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
         } else {
-            Err("Failed to get current branch".into())
+            Err(VersionItError::Git("Failed to get current branch".to_string()))
         }
     }
 
-    pub fn get_latest_version_tag(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    /// Returns the suffix of snapshot versions, `snapshot-suffix` or `-SNAPSHOT`.
+    pub fn snapshot_suffix(&self) -> &str {
+        self.snapshot_suffix.as_deref().unwrap_or(DEFAULT_SNAPSHOT_SUFFIX)
+    }
+
+    /// Finds the latest version tag, skipping prerelease tags when
+    /// `ignore-prerelease-tags` is set.
+    pub fn get_latest_version_tag(&self) -> Result<Option<String>, VersionItError> {
+        self.latest_version_tag(!self.ignore_prerelease_tags)
+    }
+
+    /// Finds the latest version tag that is not a prerelease, e.g. `1.2.0` rather
+    /// than a later `1.3.0-beta.1`.
+    pub fn get_latest_stable_version_tag(&self) -> Result<Option<String>, VersionItError> {
+        self.latest_version_tag(false)
+    }
+
+    fn latest_version_tag(&self, include_prereleases: bool) -> Result<Option<String>, VersionItError> {
         let output = Command::new("git").args(["tag", "--list", "--sort=-version:refname"]).output()?;
         if output.status.success() {
             let tags = String::from_utf8_lossy(&output.stdout);
-            for tag in tags.lines() {
-                if self.is_version_tag(tag) {
-                    return Ok(Some(tag.to_string()));
-                }
-            }
+            return Ok(self.latest_of(tags.lines(), include_prereleases).map(|tag| tag.to_string()));
         }
         Ok(None)
     }
 
+    /// Picks the latest version tag from tags listed newest first by git's version sort.
+    ///
+    /// Git sorts `1.2.0-beta.1` after `1.2.0`, so semantic tags are compared by semver
+    /// precedence instead, where a prerelease comes before its release.
+    ///
+    /// With `mixed-tag-prefixes`, git's order is ignored: the prefixes are stripped, the
+    /// versions compared, and of several tags for the same version the shortest wins,
+    /// so `1.2.0` is preferred over `v1.2.0` and `release/1.2.0`.
+    fn latest_of<'a>(&self, tags: impl Iterator<Item = &'a str>, include_prereleases: bool) -> Option<&'a str> {
+        if self.mixed_tag_prefixes {
+            return tags
+                .filter_map(|tag| {
+                    let version = self.tag_version_str(tag);
+                    if !include_prereleases && self.is_prerelease_tag(version) {
+                        return None;
+                    }
+                    VersionInfo::new(version, self.versioning_scheme, None).ok().map(|info| (tag, info))
+                })
+                .max_by(|(a, a_info), (b, b_info)| {
+                    a_info.compare(b_info).unwrap_or(std::cmp::Ordering::Equal).then_with(|| b.len().cmp(&a.len()))
+                })
+                .map(|(tag, _)| tag);
+        }
+        let mut tags = tags.filter(|tag| self.is_version_tag(tag) && (include_prereleases || !self.is_prerelease_tag(tag)));
+        match self.versioning_scheme {
+            Scheme::Semantic => tags.max_by_key(|tag| semver::Version::parse(tag).ok()),
+            _ => tags.next(),
+        }
+    }
+
+    /// Returns the version part of a tag: the tag itself, or with `mixed-tag-prefixes`
+    /// everything from the first digit on, e.g. `1.2.0` for `release/1.2.0`.
+    pub fn tag_version_str<'a>(&self, tag: &'a str) -> &'a str {
+        if !self.mixed_tag_prefixes {
+            return tag;
+        }
+        tag.find(|c: char| c.is_ascii_digit()).map_or(tag, |start| &tag[start..])
+    }
+
     fn is_version_tag(&self, tag: &str) -> bool {
-        match self.versioning_scheme.as_str() {
-            "semantic" => semver::Version::parse(tag).is_ok(),
-            "calver" => tag.contains('.') && tag.chars().all(|c| c.is_ascii_digit() || c == '.'),
+        match self.versioning_scheme {
+            Scheme::Semantic => semver::Version::parse(tag).is_ok(),
+            Scheme::Calver => tag.contains('.') && tag.chars().all(|c| c.is_ascii_digit() || c == '.'),
             _ => true, // for others, assume any tag
         }
     }
 
-    fn get_commits_since(&self, since: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-        let output = Command::new("git").args(["log", "--oneline", &format!("{}..HEAD", since)]).output()?;
+    /// Whether a version tag is a semver prerelease such as `1.3.0-beta.1`.
+    fn is_prerelease_tag(&self, tag: &str) -> bool {
+        match self.versioning_scheme {
+            Scheme::Semantic => semver::Version::parse(tag).is_ok_and(|v| !v.pre.is_empty()),
+            _ => false,
+        }
+    }
+
+    fn get_commits_since(&self, since: &str) -> Result<Vec<String>, VersionItError> {
+        let range = format!("{}..HEAD", since);
+        let mut args = vec!["log", "--oneline"];
+        if self.commit_analysis_first_parent {
+            args.push("--first-parent");
+        }
+        args.push(&range);
+        let output = Command::new("git").args(&args).output()?;
         if output.status.success() {
             let commits = String::from_utf8_lossy(&output.stdout);
             Ok(commits.lines().map(|l| l.to_string()).collect())
@@ -196,31 +956,37 @@ impl Config {
     }
 
     fn determine_bump_from_commit(&self, commit: &str) -> Option<String> {
+        self.classify_commit(commit).action
+    }
+
+    /// Finds the first `change-type-map` entry with a bump action matching the commit,
+    /// or else the first matching entry with the null action.
+    fn classify_commit(&self, commit: &str) -> CommitClassification {
+        let mut ignored_by: Option<String> = None;
         // Check for labels/patterns in commit message
         for map in &self.change_type_map {
-            let matches = if let Some(ref pattern) = map.pattern {
-                // Use regex matching
-                if let Ok(re) = regex::Regex::new(pattern) {
-                    re.is_match(commit)
-                } else {
-                    // If regex is invalid, fall back to simple contains
-                    commit.contains(&map.label)
-                }
-            } else {
-                // Use simple string contains for backward compatibility
-                commit.contains(&map.label)
-            };
-
-            if matches {
-                match map.action {
-                    ChangeAction::Minor => return Some("minor".to_string()),
-                    ChangeAction::Patch => return Some("patch".to_string()),
-                    ChangeAction::Major => return Some("major".to_string()),
-                    ChangeAction::Null => {},
-                }
+            if map.matches(commit) {
+                let action = match map.action {
+                    ChangeAction::Minor => "minor",
+                    ChangeAction::Patch => "patch",
+                    ChangeAction::Major => "major",
+                    ChangeAction::Null => {
+                        ignored_by.get_or_insert_with(|| map.label.clone());
+                        continue;
+                    }
+                };
+                return CommitClassification {
+                    commit: commit.to_string(),
+                    matched_label: Some(map.label.clone()),
+                    action: Some(action.to_string()),
+                };
             }
         }
-        None
+        CommitClassification {
+            commit: commit.to_string(),
+            matched_label: ignored_by,
+            action: None,
+        }
     }
 
     fn higher_bump(&self, a: Option<&str>, b: Option<&str>) -> Option<String> {
@@ -270,21 +1036,171 @@ enable-expensive-metrics: false
 "#;
         fs::write("test_config.yml", yaml).unwrap();
         let config = Config::load_from_file("test_config.yml").unwrap();
-        assert_eq!(config.versioning_scheme, "semantic");
+        assert_eq!(config.versioning_scheme, Scheme::Semantic);
         assert_eq!(config.first_version, "1.0.0");
+        assert_eq!(config.base_path, None);
         fs::remove_file("test_config.yml").unwrap();
     }
 
+    #[test]
+    fn test_load_from_file_strict_rejects_unknown_keys() {
+        use std::fs;
+        let yaml = r#"
+run-on-branches: []
+versioning-scheme: semantic
+versioning_schema: calver
+first-version: "1.0.0"
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map:
+  - label: feat
+    action: minor
+    patern: "feat.*"
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#;
+        fs::write("test_strict_config.yml", yaml).unwrap();
+        let lenient = Config::load_from_file("test_strict_config.yml");
+        let strict = Config::load_from_file_strict("test_strict_config.yml");
+        fs::remove_file("test_strict_config.yml").unwrap();
+
+        assert!(lenient.is_ok());
+        let err = strict.unwrap_err().to_string();
+        assert!(err.contains("versioning_schema"));
+        assert!(err.contains("change-type-map.0.patern"));
+    }
+
+    #[test]
+    fn test_load_from_str_detects_json() {
+        let json = r#"{"run-on-branches": [], "versioning-scheme": "calver", "first-version": "24.01.01", "calver-enable-branch": false,
+            "changelog-sections": [], "change-substitutions": [], "change-type-map": [], "commit-based-bumping": false, "enable-expensive-metrics": false}"#;
+        let yaml = "run-on-branches: []\nversioning-scheme: semantic\nfirst-version: 1.0.0\ncalver-enable-branch: false\nchangelog-sections: []\nchange-substitutions: []\nchange-type-map: []\ncommit-based-bumping: false\nenable-expensive-metrics: false\nunknown-key: 1\n";
+
+        let config = Config::load_from_str(json, false).unwrap();
+        assert_eq!(config.versioning_scheme, Scheme::Calver);
+        assert_eq!(config.base_path, None);
+        assert_eq!(Config::load_from_str(yaml, false).unwrap().first_version, "1.0.0");
+        assert!(Config::load_from_str(yaml, true).unwrap_err().to_string().contains("unknown-key"));
+        assert!(Config::load_from_str("{\"first-version\": ", false).unwrap_err().to_string().starts_with("Invalid JSON config"));
+    }
+
+    #[test]
+    fn test_load_from_file_sets_base_path() {
+        use std::fs;
+        fs::create_dir_all("test_base_path_dir").unwrap();
+        fs::write("test_base_path_dir/.version-it", r#"
+run-on-branches: []
+versioning-scheme: semantic
+first-version: "1.0.0"
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#).unwrap();
+        let config = Config::load_from_file("test_base_path_dir/.version-it");
+        fs::remove_dir_all("test_base_path_dir").unwrap();
+        let config = config.unwrap();
+        assert_eq!(config.base_path, Some(PathBuf::from("test_base_path_dir")));
+        assert_eq!(config.resolve_path("version.txt"), PathBuf::from("test_base_path_dir/version.txt"));
+    }
+
+    #[test]
+    fn test_load_from_file_error_kinds() {
+        use std::fs;
+        let result = Config::load_from_file("does_not_exist.yml");
+        assert!(matches!(result, Err(VersionItError::Io(_))));
+
+        fs::write("test_invalid_config.yml", "versioning-scheme: [unterminated").unwrap();
+        let result = Config::load_from_file("test_invalid_config.yml");
+        fs::remove_file("test_invalid_config.yml").unwrap();
+        assert!(matches!(result, Err(VersionItError::Config(_))));
+    }
+
+    #[test]
+    fn test_unknown_scheme_fails_to_load() {
+        let yaml = r#"
+run-on-branches: [main]
+versioning-scheme: semver
+first-version: 1.0.0
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#;
+        let error = serde_yaml::from_str::<Config>(yaml).unwrap_err().to_string();
+        assert!(error.contains("unknown variant `semver`"), "{}", error);
+    }
+
+    #[test]
+    fn test_expand_env() {
+        let lookup = |name: &str| match name {
+            "BASE" => Some("2.0.0".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        assert_eq!(expand_env("${BASE}", &lookup).unwrap(), "2.0.0");
+        assert_eq!(expand_env("v${BASE}-${MISSING:-dev}", &lookup).unwrap(), "v2.0.0-dev");
+        assert_eq!(expand_env("${EMPTY:-1.0.0}", &lookup).unwrap(), "1.0.0");
+        assert_eq!(expand_env("cost $5, $${BASE}", &lookup).unwrap(), "cost $5, ${BASE}");
+        assert!(expand_env("${MISSING}", &lookup).unwrap_err().to_string().contains("'MISSING'"));
+        assert!(expand_env("${BASE", &lookup).is_err());
+
+        let mut value: serde_yaml::Value = serde_yaml::from_str("first-version: ${BASE}\nmonotonic-step: 10\ntags: ['${MISSING:-x}']\n").unwrap();
+        expand_env_in_value(&mut value, &lookup).unwrap();
+        assert_eq!(value["first-version"], serde_yaml::Value::from("2.0.0"));
+        assert_eq!(value["monotonic-step"], serde_yaml::Value::from(10));
+        assert_eq!(value["tags"][0], serde_yaml::Value::from("x"));
+    }
+
+    #[test]
+    fn test_version_source_forms() {
+        let base = r#"
+run-on-branches: [main]
+versioning-scheme: semantic
+first-version: 1.0.0
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#;
+        let cargo: Config = serde_yaml::from_str(&format!("{}version-source: cargo\n", base)).unwrap();
+        let package: Config = serde_yaml::from_str(&format!("{}version-source:\n  package:\n    path: pyproject.toml\n    manager: python\n", base)).unwrap();
+        let glob: Config = serde_yaml::from_str(&format!("{}version-source:\n  package:\n    path: '*/package.json'\n    manager: npm\n", base)).unwrap();
+
+        assert_eq!(cargo.version_source, VersionSource::Cargo);
+        assert_eq!(cargo.version_source.package_file().map(|p| p.path), Some("Cargo.toml".to_string()));
+        assert_eq!(package.version_source.package_file().map(|p| p.manager), Some("python".to_string()));
+        assert!(serde_yaml::to_string(&package).unwrap().contains("package:"));
+        assert!(glob.validate().is_err());
+    }
+
     #[test]
     fn test_get_current_version_from_file() {
         use std::fs;
         fs::write("test_version.txt", "2.1.0\n").unwrap();
         let config = Config {
             run_on_branches: vec![],
-            versioning_scheme: "semantic".to_string(),
+            versioning_scheme: Scheme::Semantic,
+            calver_format: CalverFormat::YearMonthDay,
+            version_pattern: None,
             first_version: "1.0.0".to_string(),
+            version_resolution_order: vec![],
             current_version_file: Some("test_version.txt".to_string()),
-            changelog_exporters: None,
+            channel_version_files: None,
+            version_source: VersionSource::File,
+            emit_checksum: false,
+            line_ending: None,
+            changelog_exporters: vec![],
+            changelog_mode: ChangelogMode::Prepend,
+            changelog_marker: None,
+            repo_url: None,
             calver_enable_branch: false,
             changelog_sections: vec![],
             change_substitutions: vec![],
@@ -292,23 +1208,64 @@ enable-expensive-metrics: false
             version_headers: None,
             package_files: None,
             channel: None,
+            channel_separator: None,
             commit_based_bumping: false,
+            max_auto_bump: None,
+            snapshot_suffix: None,
+            commit_analysis_first_parent: false,
             enable_expensive_metrics: false,
             structured_output: false,
+            monotonic_step: None,
+            enforce_branches: false,
+            ignore_prerelease_tags: false,
+            mixed_tag_prefixes: false,
+            git_retries: None,
+            base_path: None,
         };
         let version = config.get_current_version().unwrap();
         assert_eq!(version, "2.1.0");
         fs::remove_file("test_version.txt").unwrap();
     }
 
+    #[test]
+    fn test_version_file_for_channel() {
+        let config: Config = serde_yaml::from_str(r#"
+run-on-branches: []
+versioning-scheme: semantic
+first-version: "1.0.0"
+current-version-file: version.txt
+channel-version-files:
+  beta: version-beta.txt
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#).unwrap();
+        assert_eq!(config.version_file_for(Some("beta")), Some("version-beta.txt"));
+        assert_eq!(config.version_file_for(Some("nightly")), Some("version.txt"));
+        assert_eq!(config.version_file_for(None), Some("version.txt"));
+    }
+
     #[test]
     fn test_determine_bump_from_commit_with_regex() {
         let config = Config {
             run_on_branches: vec![],
-            versioning_scheme: "semantic".to_string(),
+            versioning_scheme: Scheme::Semantic,
+            calver_format: CalverFormat::YearMonthDay,
+            version_pattern: None,
             first_version: "1.0.0".to_string(),
+            version_resolution_order: vec![],
             current_version_file: None,
-            changelog_exporters: None,
+            channel_version_files: None,
+            version_source: VersionSource::File,
+            emit_checksum: false,
+            line_ending: None,
+            changelog_exporters: vec![],
+            changelog_mode: ChangelogMode::Prepend,
+            changelog_marker: None,
+            repo_url: None,
             calver_enable_branch: false,
             changelog_sections: vec![],
             change_substitutions: vec![],
@@ -317,23 +1274,308 @@ enable-expensive-metrics: false
                     label: "feat".to_string(),
                     pattern: Some(r"feat.*".to_string()),
                     action: ChangeAction::Minor,
+                    case_insensitive: false,
+                    anchored: false,
                 },
                 ChangeTypeMap {
                     label: "fix".to_string(),
                     pattern: Some(r"fix.*bug".to_string()),
                     action: ChangeAction::Patch,
+                    case_insensitive: false,
+                    anchored: false,
                 },
             ],
             version_headers: None,
             package_files: None,
             channel: None,
+            channel_separator: None,
             commit_based_bumping: true,
+            max_auto_bump: None,
+            snapshot_suffix: None,
+            commit_analysis_first_parent: false,
             enable_expensive_metrics: false,
             structured_output: false,
+            monotonic_step: None,
+            enforce_branches: false,
+            ignore_prerelease_tags: false,
+            mixed_tag_prefixes: false,
+            git_retries: None,
+            base_path: None,
         };
 
         assert_eq!(config.determine_bump_from_commit("feat: add new feature"), Some("minor".to_string()));
         assert_eq!(config.determine_bump_from_commit("fix: critical bug fix"), Some("patch".to_string()));
         assert_eq!(config.determine_bump_from_commit("fix: typo fix"), None);
     }
+
+    #[test]
+    fn test_change_type_map_case_insensitive_and_anchored() {
+        let yaml = r#"
+run-on-branches: [main]
+versioning-scheme: semantic
+first-version: 1.0.0
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map:
+- label: feat
+  pattern: "feat(\\(.*\\))?:"
+  action: minor
+  case-insensitive: true
+  anchored: true
+- label: fix
+  action: patch
+  anchored: true
+commit-based-bumping: true
+enable-expensive-metrics: false
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.determine_bump_from_commit("1a2b3c4 Feat: shiny thing"), Some("minor".to_string()));
+        assert_eq!(config.determine_bump_from_commit("1a2b3c4 FEAT(cli): shiny thing"), Some("minor".to_string()));
+        assert_eq!(config.determine_bump_from_commit("1a2b3c4 docs: explain feat: usage"), None);
+        assert_eq!(config.determine_bump_from_commit("fix: crash"), Some("patch".to_string()));
+        assert_eq!(config.determine_bump_from_commit("1a2b3c4 chore: prefix fix"), None);
+    }
+
+    #[test]
+    fn test_latest_of_orders_prereleases_by_semver() {
+        let yaml = r#"
+run-on-branches: [main]
+versioning-scheme: semantic
+first-version: 1.0.0
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        // As listed by git tag --sort=-version:refname
+        let tags = ["1.3.0-beta.1", "1.2.0-beta.2", "1.2.0", "1.2.0-beta.1", "not-a-version", "1.1.0"];
+        assert_eq!(config.latest_of(tags.into_iter(), true), Some("1.3.0-beta.1"));
+        assert_eq!(config.latest_of(tags.into_iter(), false), Some("1.2.0"));
+        assert_eq!(config.latest_of(["1.2.0-beta.2", "1.2.0"].into_iter(), true), Some("1.2.0"));
+    }
+
+    #[test]
+    fn test_resolve_current_version_order() {
+        let yaml = r#"
+run-on-branches: [main]
+versioning-scheme: semantic
+first-version: 1.0.0
+current-version-file: test_resolution_order.txt
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        // Without the tag step, so the result doesn't depend on this checkout's tags
+        config.version_resolution_order = vec![VersionResolutionStep::Source, VersionResolutionStep::FirstVersion];
+        let missing = config.resolve_current_version(None);
+        std::fs::write("test_resolution_order.txt", "2.3.4\n").unwrap();
+        let from_file = config.resolve_current_version(None);
+        config.version_resolution_order = vec![VersionResolutionStep::FirstVersion, VersionResolutionStep::Source];
+        let first = config.resolve_current_version(None);
+        std::fs::remove_file("test_resolution_order.txt").unwrap();
+        config.version_resolution_order = vec![VersionResolutionStep::Source];
+        let unresolved = config.resolve_current_version(None);
+        config.version_resolution_order = vec![VersionResolutionStep::Tag, VersionResolutionStep::Tag];
+
+        assert_eq!(missing.unwrap(), "1.0.0");
+        assert_eq!(from_file.unwrap(), "2.3.4");
+        assert_eq!(first.unwrap(), "1.0.0");
+        assert!(unresolved.is_err());
+        assert_eq!(config.validate().unwrap_err().to_string(), "version-resolution-order lists tag twice");
+    }
+
+    #[test]
+    fn test_latest_of_mixed_tag_prefixes() {
+        let yaml = r#"
+run-on-branches: [main]
+versioning-scheme: semantic
+first-version: 1.0.0
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+mixed-tag-prefixes: true
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        // Git sorts by prefix first, so the newest version isn't listed first
+        let tags = ["v1.9.0", "v1.2.0", "release/1.10.0", "release/2.0.0-rc.1", "1.2.0", "v1.10.0", "nightly"];
+        assert_eq!(config.latest_of(tags.into_iter(), true), Some("release/2.0.0-rc.1"));
+        assert_eq!(config.latest_of(tags.into_iter(), false), Some("v1.10.0"));
+        assert_eq!(config.latest_of(["v1.2.0", "1.2.0", "release/1.2.0"].into_iter(), true), Some("1.2.0"));
+        assert_eq!(config.tag_version_str("release/1.10.0"), "1.10.0");
+        assert_eq!(config.tag_version_str("nightly"), "nightly");
+    }
+
+    #[test]
+    fn test_cap_auto_bump() {
+        let yaml = r#"
+run-on-branches: [main]
+versioning-scheme: semantic
+first-version: 1.0.0
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: true
+max-auto-bump: minor
+enable-expensive-metrics: false
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.cap_auto_bump("major".to_string()), "minor");
+        assert_eq!(config.cap_auto_bump("minor".to_string()), "minor");
+        assert_eq!(config.cap_auto_bump("patch".to_string()), "patch");
+        config.max_auto_bump = Some("huge".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_classify_commit() {
+        let config: Config = serde_yaml::from_str(r#"
+run-on-branches: []
+versioning-scheme: semantic
+first-version: "1.0.0"
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map:
+  - label: chore
+    action: null
+  - label: feat
+    action: minor
+commit-based-bumping: true
+enable-expensive-metrics: false
+"#).unwrap();
+        let classified = config.classify_commit("abc1234 chore: feat flag cleanup");
+        assert_eq!(classified.matched_label.as_deref(), Some("feat"));
+        assert_eq!(classified.action.as_deref(), Some("minor"));
+
+        let classified = config.classify_commit("abc1234 chore: bump deps");
+        assert_eq!(classified.matched_label.as_deref(), Some("chore"));
+        assert_eq!(classified.action, None);
+
+        let classified = config.classify_commit("abc1234 docs: typo");
+        assert_eq!(classified.matched_label, None);
+        assert_eq!(classified.action, None);
+    }
+
+    #[test]
+    fn test_validate_regex_package_file() {
+        let mut config: Config = serde_yaml::from_str(r#"
+run-on-branches: []
+versioning-scheme: semantic
+first-version: "1.0.0"
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+package-files:
+  - path: build.sh
+    manager: regex
+    pattern: 'VERSION="([^"]*)"'
+"#).unwrap();
+        assert!(config.validate().is_ok());
+
+        config.package_files.as_mut().unwrap()[0].pattern = Some(r#"VERSION="[^"]*""#.to_string());
+        assert!(config.validate().is_err());
+
+        config.package_files.as_mut().unwrap()[0].pattern = None;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_changelog_exporter() {
+        let mut config: Config = serde_yaml::from_str(r#"
+run-on-branches: []
+versioning-scheme: semantic
+first-version: "1.0.0"
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+changelog-exporters:
+  format: keep-a-changelog
+  output-path: CHANGELOG.md
+"#).unwrap();
+        assert!(config.validate().is_ok());
+
+        config.changelog_exporters[0].format = Some("markdown".to_string());
+        assert!(config.validate().is_err());
+
+        config.changelog_exporters[0].format = None;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_changelog_exporters_list() {
+        let config: Config = serde_yaml::from_str(r#"
+run-on-branches: []
+versioning-scheme: semantic
+first-version: "1.0.0"
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+changelog-exporters:
+- format: keep-a-changelog
+  output-path: CHANGELOG.md
+- template-path: release.json.hbs
+  output-path: release.json
+  mode: overwrite
+"#).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.changelog_exporters.len(), 2);
+        assert_eq!(config.changelog_exporters[1].output_path, "release.json");
+        assert_eq!(config.changelog_exporters[1].mode, Some(ChangelogMode::Overwrite));
+
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        let reparsed: Config = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(reparsed.changelog_exporters.len(), 2);
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = Config::json_schema();
+        let properties = &schema["properties"];
+        assert!(properties["versioning-scheme"]["enum"].as_array().unwrap().contains(&serde_json::json!("semantic-commit")));
+        assert!(properties.get("versioning_scheme").is_none());
+        assert!(properties.get("base_path").is_none());
+        let actions = schema["definitions"]["ChangeAction"]["enum"].as_array().unwrap();
+        assert!(actions.contains(&serde_json::json!("null")));
+    }
+
+    #[test]
+    fn test_resolve_path_with_base_path() {
+        let mut config: Config = serde_yaml::from_str(r#"
+run-on-branches: []
+versioning-scheme: semantic
+first-version: "1.0.0"
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#).unwrap();
+        assert_eq!(config.resolve_path("version.txt"), PathBuf::from("version.txt"));
+
+        config.base_path = Some(PathBuf::from("/repo"));
+        assert_eq!(config.resolve_path("version.txt"), PathBuf::from("/repo/version.txt"));
+        assert_eq!(config.resolve_path("/abs/version.txt"), PathBuf::from("/abs/version.txt"));
+    }
 }
\ No newline at end of file
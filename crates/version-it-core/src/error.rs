@@ -0,0 +1,138 @@
+use std::fmt;
+
+/// Errors returned by the version-it core APIs.
+#[derive(Debug)]
+pub enum VersionItError {
+    /// The config file is malformed or contains invalid settings.
+    Config(String),
+    /// An operation needs a config, or a setting in it, and none was found.
+    ConfigNotFound(String),
+    /// The options of a command conflict or don't apply, e.g. an unknown bump type.
+    InvalidArgument(String),
+    /// A git command failed or returned unexpected output.
+    Git(String),
+    /// A version string could not be parsed or modified for its scheme.
+    VersionParse(String),
+    /// A package file could not be parsed or updated.
+    PackageUpdate(String),
+    /// A version header template could not be loaded or rendered.
+    Template(String),
+    /// Reading or writing a file failed.
+    Io(std::io::Error),
+}
+
+impl VersionItError {
+    /// A stable machine-readable code for the kind of error, e.g. `CONFIG_INVALID`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            VersionItError::Config(_) => "CONFIG_INVALID",
+            VersionItError::ConfigNotFound(_) => "CONFIG_NOT_FOUND",
+            VersionItError::InvalidArgument(_) => "INVALID_ARGUMENT",
+            VersionItError::Git(_) => "GIT_ERROR",
+            VersionItError::VersionParse(_) => "VERSION_PARSE_ERROR",
+            VersionItError::PackageUpdate(_) => "PACKAGE_UPDATE_ERROR",
+            VersionItError::Template(_) => "TEMPLATE_ERROR",
+            VersionItError::Io(_) => "IO_ERROR",
+        }
+    }
+
+    /// Prefixes the message with what was being done, e.g. `Error writing version file`,
+    /// keeping the kind of error.
+    pub fn context(self, context: &str) -> Self {
+        let wrap = |msg: String| format!("{}: {}", context, msg);
+        match self {
+            VersionItError::Config(msg) => VersionItError::Config(wrap(msg)),
+            VersionItError::ConfigNotFound(msg) => VersionItError::ConfigNotFound(wrap(msg)),
+            VersionItError::InvalidArgument(msg) => VersionItError::InvalidArgument(wrap(msg)),
+            VersionItError::Git(msg) => VersionItError::Git(wrap(msg)),
+            VersionItError::VersionParse(msg) => VersionItError::VersionParse(wrap(msg)),
+            VersionItError::PackageUpdate(msg) => VersionItError::PackageUpdate(wrap(msg)),
+            VersionItError::Template(msg) => VersionItError::Template(wrap(msg)),
+            VersionItError::Io(e) => VersionItError::Io(std::io::Error::new(e.kind(), wrap(e.to_string()))),
+        }
+    }
+}
+
+impl fmt::Display for VersionItError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionItError::Config(msg)
+            | VersionItError::ConfigNotFound(msg)
+            | VersionItError::InvalidArgument(msg)
+            | VersionItError::Git(msg)
+            | VersionItError::VersionParse(msg)
+            | VersionItError::PackageUpdate(msg)
+            | VersionItError::Template(msg) => write!(f, "{}", msg),
+            VersionItError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for VersionItError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VersionItError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for VersionItError {
+    fn from(e: std::io::Error) -> Self {
+        VersionItError::Io(e)
+    }
+}
+
+impl From<serde_yaml::Error> for VersionItError {
+    fn from(e: serde_yaml::Error) -> Self {
+        VersionItError::Config(e.to_string())
+    }
+}
+
+impl From<semver::Error> for VersionItError {
+    fn from(e: semver::Error) -> Self {
+        VersionItError::VersionParse(e.to_string())
+    }
+}
+
+impl From<std::num::ParseIntError> for VersionItError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        VersionItError::VersionParse(e.to_string())
+    }
+}
+
+impl From<std::num::TryFromIntError> for VersionItError {
+    fn from(e: std::num::TryFromIntError) -> Self {
+        VersionItError::VersionParse(e.to_string())
+    }
+}
+
+impl From<handlebars::RenderError> for VersionItError {
+    fn from(e: handlebars::RenderError) -> Self {
+        VersionItError::Template(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for VersionItError {
+    fn from(e: serde_json::Error) -> Self {
+        VersionItError::PackageUpdate(e.to_string())
+    }
+}
+
+impl From<toml::de::Error> for VersionItError {
+    fn from(e: toml::de::Error) -> Self {
+        VersionItError::PackageUpdate(e.to_string())
+    }
+}
+
+impl From<toml::ser::Error> for VersionItError {
+    fn from(e: toml::ser::Error) -> Self {
+        VersionItError::PackageUpdate(e.to_string())
+    }
+}
+
+impl From<regex::Error> for VersionItError {
+    fn from(e: regex::Error) -> Self {
+        VersionItError::PackageUpdate(e.to_string())
+    }
+}
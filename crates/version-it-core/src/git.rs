@@ -1,26 +1,27 @@
 use std::process::Command;
 use serde_json;
+use crate::error::VersionItError;
 
 impl super::Config {
-    fn current_commit_full() -> Result<String, Box<dyn std::error::Error>> {
+    fn current_commit_full() -> Result<String, VersionItError> {
         let output = Command::new("git").args(["rev-parse", "HEAD"]).output()?;
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
         } else {
-            Err("Failed to get git commit".into())
+            Err(VersionItError::Git("Failed to get git commit".to_string()))
         }
     }
 
-    fn current_branch() -> Result<String, Box<dyn std::error::Error>> {
+    fn current_branch() -> Result<String, VersionItError> {
         let output = Command::new("git").args(["rev-parse", "--abbrev-ref", "HEAD"]).output()?;
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
         } else {
-            Err("Failed to get git branch".into())
+            Err(VersionItError::Git("Failed to get git branch".to_string()))
         }
     }
 
-    fn latest_tag() -> Result<String, Box<dyn std::error::Error>> {
+    fn latest_tag() -> Result<String, VersionItError> {
         let output = Command::new("git").args(["describe", "--tags", "--abbrev=0"]).output()?;
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
@@ -29,34 +30,34 @@ impl super::Config {
         }
     }
 
-    fn commit_author() -> Result<String, Box<dyn std::error::Error>> {
+    fn commit_author() -> Result<String, VersionItError> {
         let output = Command::new("git").args(["log", "-1", "--pretty=format:%an"]).output()?;
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
         } else {
-            Err("Failed to get commit author".into())
+            Err(VersionItError::Git("Failed to get commit author".to_string()))
         }
     }
 
-    fn commit_email() -> Result<String, Box<dyn std::error::Error>> {
+    fn commit_email() -> Result<String, VersionItError> {
         let output = Command::new("git").args(["log", "-1", "--pretty=format:%ae"]).output()?;
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
         } else {
-            Err("Failed to get commit email".into())
+            Err(VersionItError::Git("Failed to get commit email".to_string()))
         }
     }
 
-    fn commit_date() -> Result<String, Box<dyn std::error::Error>> {
+    fn commit_date() -> Result<String, VersionItError> {
         let output = Command::new("git").args(["log", "-1", "--pretty=format:%ci"]).output()?;
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
         } else {
-            Err("Failed to get commit date".into())
+            Err(VersionItError::Git("Failed to get commit date".to_string()))
         }
     }
 
-    fn recent_commits(limit: usize) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
+    fn recent_commits(limit: usize) -> Result<Vec<serde_json::Value>, VersionItError> {
         let output = Command::new("git")
             .args(["log", &format!("-{}", limit), "--oneline", "--pretty=format:%H|%h|%s|%an|%ae|%ci"])
             .output()?;
@@ -85,7 +86,7 @@ impl super::Config {
         Ok(result)
     }
 
-    fn commit_count() -> Result<u64, Box<dyn std::error::Error>> {
+    fn commit_count() -> Result<u64, VersionItError> {
         let output = Command::new("git")
             .args(["rev-list", "--count", "HEAD"])
             .output()?;
@@ -98,7 +99,7 @@ impl super::Config {
         }
     }
 
-    fn first_commit_date() -> Result<String, Box<dyn std::error::Error>> {
+    fn first_commit_date() -> Result<String, VersionItError> {
         let output = Command::new("git")
             .args(["log", "--reverse", "--pretty=format:%ci", "-1"])
             .output()?;
@@ -4,7 +4,11 @@ pub mod git;
 pub mod templates;
 pub mod package;
 pub mod utils;
+pub mod error;
+pub mod changelog;
 
 // Re-export public items
-pub use version::{VersionInfo, VersionType};
-pub use config::{Config, ChangelogExporters, ChangelogSection, ChangeSubstitution, ChangeAction, ChangeTypeMap, VersionHeader, PackageFile};
\ No newline at end of file
+pub use version::{CalverFormat, PatternVersion, Scheme, VersionInfo, VersionType};
+pub use error::VersionItError;
+pub use changelog::{Changelog, ChangelogMode};
+pub use config::{Config, VersionSource, ChangelogExporters, ChangelogSection, ChangeSubstitution, ChangeAction, ChangeTypeMap, CommitClassification, VersionHeader, PackageFile, LineEnding, VersionResolutionStep};
\ No newline at end of file
@@ -1,7 +1,171 @@
 use regex;
 use toml;
+use crate::error::VersionItError;
+use crate::templates::render_package_version;
+use crate::utils::{detect_indent, expand_glob, normalize_line_endings, write_atomic};
+use std::path::{Path, PathBuf};
+
+/// Names of the supported `package-files` managers.
+pub const PACKAGE_MANAGERS: &[&str] = &[
+    "npm", "yarn", "pnpm", "cargo", "python", "pyproject", "setup-cfg", "setup-py", "helm", "maven", "regex",
+];
+
+/// Pretty-prints JSON with the indentation of the file it replaces (two spaces if it
+/// has none), keeping its trailing newline.
+fn to_json_like(json: &serde_json::Value, original: &str) -> Result<String, VersionItError> {
+    use serde::Serialize;
+    let indent = detect_indent(original).unwrap_or("  ");
+    let mut buf = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buf, serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes()));
+    json.serialize(&mut serializer)?;
+    let mut updated = String::from_utf8_lossy(&buf).into_owned();
+    if original.ends_with('\n') {
+        updated.push('\n');
+    }
+    Ok(updated)
+}
+
+/// Returns the `package-lock.json` next to a `package.json`, if there is one.
+pub fn npm_lockfile(package_json: &Path) -> Option<PathBuf> {
+    let lockfile = package_json.with_file_name("package-lock.json");
+    lockfile.exists().then_some(lockfile)
+}
+
+/// Keeps the operator of a version requirement such as `^1.2.0` or `>= 1.2`.
+fn with_requirement_prefix(current: &str, version: &str) -> String {
+    let prefix_len = current.len() - current.trim_start_matches(['^', '~', '>', '<', '=', ' ']).len();
+    format!("{}{}", &current[..prefix_len], version)
+}
+
+/// Returns the dotted field holding the version of a `Cargo.toml`: `package.version`,
+/// or `workspace.package.version` for workspace manifests and inherited versions.
+fn cargo_version_field(manifest: &toml::Value) -> &'static str {
+    match manifest.get("package").and_then(|p| p.get("version")) {
+        Some(toml::Value::String(_)) => "package.version",
+        _ => "workspace.package.version",
+    }
+}
+
+/// Looks up a dotted field such as `package.version` in a TOML document.
+fn toml_field<'a>(value: &'a toml::Value, field: &str) -> Option<&'a toml::Value> {
+    field.split('.').try_fold(value, |current, key| current.get(key))
+}
+
+/// Returns the field holding the version of a `pyproject.toml`: PEP 621 `project.version`,
+/// or Poetry's `tool.poetry.version`.
+fn pyproject_version_field(pyproject: &toml::Value) -> &'static str {
+    let is_poetry = pyproject.get("project").is_none()
+        && pyproject.get("tool").and_then(|t| t.get("poetry")).is_some();
+    if is_poetry { "tool.poetry.version" } else { "project.version" }
+}
+
+/// Reads a dotted TOML field, or the one picked by `default_field` when none is given.
+fn read_toml_field(content: &str, field: Option<&str>, default_field: fn(&toml::Value) -> &'static str) -> Result<Option<String>, VersionItError> {
+    let value: toml::Value = toml::from_str(content)?;
+    let field = field.unwrap_or_else(|| default_field(&value));
+    Ok(toml_field(&value, field).and_then(|v| v.as_str()).map(|v| v.to_string()))
+}
+
+/// Reads `section.key` from a `setup.cfg` style file.
+fn read_setup_cfg_field(content: &str, field: &str) -> Option<String> {
+    let (section, key) = field.split_once('.').unwrap_or(("metadata", field));
+    let mut current_section = "";
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            current_section = trimmed[1..trimmed.len() - 1].trim();
+        } else if current_section == section {
+            if let Some((name, value)) = trimmed.split_once(['=', ':']) {
+                if name.trim() == key {
+                    return Some(value.trim().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Matches a quoted assignment or keyword argument, e.g. `__version__ = "1.2.3"`.
+fn python_assignment_pattern(field: &str) -> String {
+    format!(r#"\b{}\s*=\s*["']([^"']*)["']"#, regex::escape(field))
+}
+
+/// Returns the first capture group of the first match of `pattern`.
+fn read_regex_field(content: &str, pattern: &str) -> Result<Option<String>, VersionItError> {
+    let re = regex::Regex::new(pattern)?;
+    Ok(re.captures(content).and_then(|caps| caps.get(1)).map(|m| m.as_str().to_string()))
+}
 
 impl super::Config {
+    /// Reads the version from a structured file (for `version-source: cargo` or
+    /// `version-source: {package: ...}`).
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The file, manager and optional field holding the version.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the version, or an error if the file has none.
+    pub fn read_source_version(&self, source: &super::PackageFile) -> Result<String, VersionItError> {
+        let path = self.resolve_path(&source.path);
+        let content = std::fs::read_to_string(&path)?;
+        let field = source.field.as_deref();
+        let version = match source.manager.as_str() {
+            "npm" | "yarn" | "pnpm" => {
+                let json: serde_json::Value = serde_json::from_str(&content)?;
+                let field = field.unwrap_or("version");
+                field.split('.').try_fold(&json, |current, key| current.get(key)).and_then(|v| v.as_str()).map(|v| v.to_string())
+            }
+            "cargo" => read_toml_field(&content, field, cargo_version_field)?,
+            "pyproject" => read_toml_field(&content, field, pyproject_version_field)?,
+            "setup-cfg" => read_setup_cfg_field(&content, field.unwrap_or("metadata.version")),
+            "setup-py" => read_regex_field(&content, &python_assignment_pattern(field.unwrap_or("version")))?,
+            "python" => match path.file_name().and_then(|n| n.to_str()) {
+                Some("pyproject.toml") => read_toml_field(&content, field, pyproject_version_field)?,
+                Some("setup.cfg") => read_setup_cfg_field(&content, field.unwrap_or("metadata.version")),
+                Some("setup.py") => read_regex_field(&content, &python_assignment_pattern(field.unwrap_or("version")))?,
+                _ => read_regex_field(&content, &python_assignment_pattern(field.unwrap_or("__version__")))?,
+            },
+            "helm" => {
+                let yaml: serde_yaml::Value = serde_yaml::from_str(&content)
+                    .map_err(|e| VersionItError::PackageUpdate(e.to_string()))?;
+                yaml.get(field.unwrap_or("version")).and_then(|v| v.as_str()).map(|v| v.to_string())
+            }
+            "maven" => {
+                let field = regex::escape(field.unwrap_or("version"));
+                read_regex_field(&content, &format!("<{}>([^<]*)</{}>", field, field))?
+            }
+            "regex" => {
+                let pattern = source.pattern.as_deref()
+                    .ok_or_else(|| VersionItError::PackageUpdate(format!("Version source '{}' uses the regex manager but has no pattern", source.path)))?;
+                read_regex_field(&content, pattern)?
+            }
+            _ => return Err(VersionItError::PackageUpdate(format!("Unsupported package manager: {}", source.manager))),
+        };
+        version.map(|v| v.trim().to_string())
+            .ok_or_else(|| VersionItError::PackageUpdate(format!("No version found in '{}'", source.path)))
+    }
+
+    /// Writes the version back to the field of the structured file it is read from.
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or failure.
+    pub fn write_source_version(&self, source: &super::PackageFile, version: &str) -> Result<(), VersionItError> {
+        let path = self.resolve_path(&source.path);
+        if source.manager == "cargo" && source.field.is_none() {
+            let content = std::fs::read_to_string(&path)?;
+            let field = cargo_version_field(&toml::from_str(&content)?);
+            write_atomic(&path, self.update_toml_file(&content, version, field)?)?;
+            return Ok(());
+        }
+        if !path.exists() {
+            return Err(VersionItError::PackageUpdate(format!("Version source '{}' does not exist", source.path)));
+        }
+        self.update_package_file_at(source, &path, version)
+    }
+
     /// Updates package files with the new version.
     ///
     /// # Arguments
@@ -11,7 +175,7 @@ impl super::Config {
     /// # Returns
     ///
     /// A Result indicating success or failure.
-    pub fn update_package_files(&self, version: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn update_package_files(&self, version: &str) -> Result<(), VersionItError> {
         if let Some(package_files) = &self.package_files {
             for package_file in package_files {
                 self.update_single_package_file(package_file, version)?;
@@ -20,40 +184,156 @@ impl super::Config {
         Ok(())
     }
 
-    fn update_single_package_file(&self, package_file: &super::PackageFile, version: &str) -> Result<(), Box<dyn std::error::Error>> {
-        if !std::path::Path::new(&package_file.path).exists() {
+    fn update_single_package_file(&self, package_file: &super::PackageFile, version: &str) -> Result<(), VersionItError> {
+        // A glob path updates every matching file
+        let paths = expand_glob(&self.resolve_path(&package_file.path))
+            .map_err(|e| VersionItError::PackageUpdate(format!("Invalid glob '{}': {}", package_file.path, e)))?;
+        let version = match &package_file.version_format {
+            Some(format) => render_package_version(format, version)?,
+            None => version.to_string(),
+        };
+        for path in paths {
+            self.update_package_file_at(package_file, &path, &version)?;
+        }
+        Ok(())
+    }
+
+    fn update_package_file_at(&self, package_file: &super::PackageFile, path: &Path, version: &str) -> Result<(), VersionItError> {
+        if !path.exists() {
             // Skip files that don't exist
             return Ok(());
         }
-        let content = std::fs::read_to_string(&package_file.path)?;
+        let content = std::fs::read_to_string(path)?;
+        // Serializers write LF, so keep a file's CRLF line endings
+        let crlf = content.contains("\r\n");
         let updated_content = match package_file.manager.as_str() {
+            "npm" | "yarn" | "pnpm" if package_file.dependency.is_some() => self.update_json_dependency(&content, version, package_file.dependency.as_deref().unwrap_or_default())?,
+            "cargo" if package_file.dependency.is_some() => self.update_toml_dependency(&content, version, package_file.dependency.as_deref().unwrap_or_default())?,
+            "npm" if package_file.field.as_deref().unwrap_or("version") == "version" => {
+                if let Some(lockfile) = npm_lockfile(path) {
+                    let lock_content = std::fs::read_to_string(&lockfile)?;
+                    let updated_lock = self.update_npm_lockfile(&lock_content, version)?;
+                    write_atomic(&lockfile, normalize_line_endings(&updated_lock, lock_content.contains("\r\n")))?;
+                }
+                self.update_json_file(&content, version, "version")?
+            }
             "npm" | "yarn" | "pnpm" => self.update_json_file(&content, version, package_file.field.as_deref().unwrap_or("version"))?,
             "cargo" => self.update_toml_file(&content, version, package_file.field.as_deref().unwrap_or("version"))?,
-            "python" => self.update_python_file(&content, version, package_file.field.as_deref().unwrap_or("__version__"))?,
+            "python" => match path.file_name().and_then(|n| n.to_str()) {
+                // These are not Python assignments, so use their own formats
+                Some("pyproject.toml") => self.update_pyproject_file(&content, version, package_file.field.as_deref())?,
+                Some("setup.cfg") => self.update_setup_cfg_file(&content, version, package_file.field.as_deref().unwrap_or("metadata.version"))?,
+                Some("setup.py") => self.update_setup_py_file(&content, version, package_file.field.as_deref().unwrap_or("version"))?,
+                _ => self.update_python_file(&content, version, package_file.field.as_deref().unwrap_or("__version__"))?,
+            },
+            "pyproject" => self.update_pyproject_file(&content, version, package_file.field.as_deref())?,
+            "setup-cfg" => self.update_setup_cfg_file(&content, version, package_file.field.as_deref().unwrap_or("metadata.version"))?,
+            "setup-py" => self.update_setup_py_file(&content, version, package_file.field.as_deref().unwrap_or("version"))?,
+            "helm" => self.update_yaml_file(&content, version, package_file.field.as_deref().unwrap_or("version"))?,
             "maven" => self.update_xml_file(&content, version, package_file.field.as_deref().unwrap_or("version"))?,
-            _ => return Err(format!("Unsupported package manager: {}", package_file.manager).into()),
+            "regex" => {
+                let pattern = package_file.pattern.as_deref()
+                    .ok_or_else(|| VersionItError::PackageUpdate(format!("Package file '{}' uses the regex manager but has no pattern", package_file.path)))?;
+                self.update_regex_file(&content, version, pattern)?
+            }
+            _ => return Err(VersionItError::PackageUpdate(format!("Unsupported package manager: {}", package_file.manager))),
         };
-        std::fs::write(&package_file.path, updated_content)?;
+        write_atomic(path, if crlf { normalize_line_endings(&updated_content, true) } else { updated_content })?;
         Ok(())
     }
 
-    fn update_json_file(&self, content: &str, version: &str, field: &str) -> Result<String, Box<dyn std::error::Error>> {
+    fn update_json_file(&self, content: &str, version: &str, field: &str) -> Result<String, VersionItError> {
+        let mut json: serde_json::Value = serde_json::from_str(content)?;
+        // Walk dotted field paths like `project.version`, creating missing objects
+        let mut keys: Vec<&str> = field.split('.').collect();
+        let last = keys.pop().unwrap_or(field);
+        let mut current = &mut json;
+        for key in keys {
+            let obj = current.as_object_mut().ok_or_else(|| VersionItError::PackageUpdate(format!("Cannot set '{}': '{}' is not inside an object", field, key)))?;
+            current = obj.entry(key.to_string()).or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+        }
+        let obj = current.as_object_mut().ok_or_else(|| VersionItError::PackageUpdate(format!("Cannot set '{}': parent is not an object", field)))?;
+        obj.insert(last.to_string(), serde_json::Value::String(version.to_string()));
+        to_json_like(&json, content)
+    }
+
+    fn update_toml_file(&self, content: &str, version: &str, field: &str) -> Result<String, VersionItError> {
+        let mut toml_value: toml::Value = toml::from_str(content)?;
+        // Walk dotted field paths like `package.version`, creating missing tables
+        let mut keys: Vec<&str> = field.split('.').collect();
+        let last = keys.pop().unwrap_or(field);
+        let mut current = &mut toml_value;
+        for key in keys {
+            let table = current.as_table_mut().ok_or_else(|| VersionItError::PackageUpdate(format!("Cannot set '{}': '{}' is not inside a table", field, key)))?;
+            current = table.entry(key.to_string()).or_insert_with(|| toml::Value::Table(toml::map::Map::new()));
+        }
+        let table = current.as_table_mut().ok_or_else(|| VersionItError::PackageUpdate(format!("Cannot set '{}': parent is not a table", field)))?;
+        table.insert(last.to_string(), toml::Value::String(version.to_string()));
+        Ok(toml::to_string(&toml_value)?)
+    }
+
+    fn update_npm_lockfile(&self, content: &str, version: &str) -> Result<String, VersionItError> {
+        // The root package's version is recorded twice: at the top level and under packages[""]
         let mut json: serde_json::Value = serde_json::from_str(content)?;
         if let Some(obj) = json.as_object_mut() {
-            obj.insert(field.to_string(), serde_json::Value::String(version.to_string()));
+            obj.insert("version".to_string(), serde_json::Value::String(version.to_string()));
+        }
+        if let Some(root) = json.get_mut("packages").and_then(|p| p.get_mut("")).and_then(|r| r.as_object_mut()) {
+            root.insert("version".to_string(), serde_json::Value::String(version.to_string()));
         }
-        Ok(serde_json::to_string_pretty(&json)?)
+        to_json_like(&json, content)
     }
 
-    fn update_toml_file(&self, content: &str, version: &str, field: &str) -> Result<String, Box<dyn std::error::Error>> {
+    fn update_json_dependency(&self, content: &str, version: &str, name: &str) -> Result<String, VersionItError> {
+        let mut json: serde_json::Value = serde_json::from_str(content)?;
+        let mut found = false;
+        for section in ["dependencies", "devDependencies", "peerDependencies", "optionalDependencies"] {
+            if let Some(requirement) = json.get_mut(section).and_then(|deps| deps.get_mut(name)) {
+                if let Some(current) = requirement.as_str() {
+                    *requirement = serde_json::Value::String(with_requirement_prefix(current, version));
+                    found = true;
+                }
+            }
+        }
+        if !found {
+            return Err(VersionItError::PackageUpdate(format!("No dependency on '{}' found", name)));
+        }
+        to_json_like(&json, content)
+    }
+
+    fn update_toml_dependency(&self, content: &str, version: &str, name: &str) -> Result<String, VersionItError> {
         let mut toml_value: toml::Value = toml::from_str(content)?;
-        if let Some(table) = toml_value.as_table_mut() {
-            table.insert(field.to_string(), toml::Value::String(version.to_string()));
+        let mut found = false;
+        for section in ["dependencies", "dev-dependencies", "build-dependencies", "workspace.dependencies"] {
+            let table = section.split('.').try_fold(&mut toml_value, |value, key| value.get_mut(key));
+            let dependency = match table.and_then(|t| t.get_mut(name)) {
+                Some(dependency) => dependency,
+                None => continue,
+            };
+            // `name = "1.0"` or `name = { version = "1.0", ... }`; path-only entries have no version
+            let requirement = if dependency.is_str() { Some(dependency) } else { dependency.get_mut("version") };
+            if let Some(requirement) = requirement {
+                let current = requirement.as_str().unwrap_or_default().to_string();
+                *requirement = toml::Value::String(with_requirement_prefix(&current, version));
+                found = true;
+            }
+        }
+        if !found {
+            return Err(VersionItError::PackageUpdate(format!("No dependency on '{}' with a version found", name)));
         }
         Ok(toml::to_string(&toml_value)?)
     }
 
-    fn update_python_file(&self, content: &str, version: &str, field: &str) -> Result<String, Box<dyn std::error::Error>> {
+    fn update_yaml_file(&self, content: &str, version: &str, field: &str) -> Result<String, VersionItError> {
+        let mut yaml: serde_yaml::Value = serde_yaml::from_str(content)
+            .map_err(|e| VersionItError::PackageUpdate(e.to_string()))?;
+        let mapping = yaml.as_mapping_mut()
+            .ok_or_else(|| VersionItError::PackageUpdate("YAML document is not a mapping".to_string()))?;
+        mapping.insert(serde_yaml::Value::String(field.to_string()), serde_yaml::Value::String(version.to_string()));
+        serde_yaml::to_string(&yaml).map_err(|e| VersionItError::PackageUpdate(e.to_string()))
+    }
+
+    fn update_python_file(&self, content: &str, version: &str, field: &str) -> Result<String, VersionItError> {
         let lines: Vec<&str> = content.lines().collect();
         let mut updated_lines = Vec::new();
         let assignment_pattern = format!("{} = ", field);
@@ -76,7 +356,52 @@ impl super::Config {
         Ok(updated_lines.join("\n"))
     }
 
-    fn update_xml_file(&self, content: &str, version: &str, field: &str) -> Result<String, Box<dyn std::error::Error>> {
+    fn update_pyproject_file(&self, content: &str, version: &str, field: Option<&str>) -> Result<String, VersionItError> {
+        let field = match field {
+            Some(field) => field,
+            None => pyproject_version_field(&toml::from_str(content)?),
+        };
+        self.update_toml_file(content, version, field)
+    }
+
+    fn update_setup_cfg_file(&self, content: &str, version: &str, field: &str) -> Result<String, VersionItError> {
+        // `section.key`; edited line by line so comments and layout survive
+        let (section, key) = field.split_once('.').unwrap_or(("metadata", field));
+        let mut current_section = String::new();
+        let mut updated = false;
+        let mut updated_lines = Vec::new();
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                current_section = trimmed[1..trimmed.len() - 1].trim().to_string();
+            } else if current_section == section && !updated {
+                if let Some(sep) = line.find(['=', ':']) {
+                    if line[..sep].trim() == key {
+                        let value_start = sep + 1 + (line[sep + 1..].len() - line[sep + 1..].trim_start().len());
+                        updated_lines.push(format!("{}{}", &line[..value_start], version));
+                        updated = true;
+                        continue;
+                    }
+                }
+            }
+            updated_lines.push(line.to_string());
+        }
+        if !updated {
+            return Err(VersionItError::PackageUpdate(format!("No '{}' key in section [{}]", key, section)));
+        }
+        let mut result = updated_lines.join("\n");
+        if content.ends_with('\n') {
+            result.push('\n');
+        }
+        Ok(result)
+    }
+
+    fn update_setup_py_file(&self, content: &str, version: &str, field: &str) -> Result<String, VersionItError> {
+        // Keyword argument of setup(), e.g. `version="1.2.3"`
+        self.update_regex_file(content, version, &python_assignment_pattern(field))
+    }
+
+    fn update_xml_file(&self, content: &str, version: &str, field: &str) -> Result<String, VersionItError> {
         // Simple XML version update - this is a basic implementation
         // For more complex XML structures, a proper XML parser would be better
         let version_tag = format!("<{}>{}</{}>", field, version, field);
@@ -85,4 +410,259 @@ impl super::Config {
         let re = regex::Regex::new(&pattern)?;
         Ok(re.replace_all(content, version_tag).to_string())
     }
-}
\ No newline at end of file
+
+    fn update_regex_file(&self, content: &str, version: &str, pattern: &str) -> Result<String, VersionItError> {
+        // Replace only the first capture group of each match, keeping the surrounding text
+        let re = regex::Regex::new(pattern)?;
+        let mut result = String::with_capacity(content.len());
+        let mut last = 0;
+        let mut matched = false;
+        for caps in re.captures_iter(content) {
+            if let Some(m) = caps.get(1) {
+                result.push_str(&content[last..m.start()]);
+                result.push_str(version);
+                last = m.end();
+                matched = true;
+            }
+        }
+        if !matched {
+            return Err(VersionItError::PackageUpdate(format!("Pattern '{}' did not match any version", pattern)));
+        }
+        result.push_str(&content[last..]);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Config;
+
+    fn test_config() -> Config {
+        serde_yaml::from_str(r#"
+run-on-branches: []
+versioning-scheme: semantic
+first-version: "1.0.0"
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+"#).unwrap()
+    }
+
+    #[test]
+    fn test_update_json_top_level_field() {
+        let config = test_config();
+        let updated = config.update_json_file(r#"{"name":"app","version":"1.0.0"}"#, "1.1.0", "version").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&updated).unwrap();
+        assert_eq!(json["version"], "1.1.0");
+        assert_eq!(json["name"], "app");
+    }
+
+    #[test]
+    fn test_update_keeps_indentation_and_line_endings() {
+        use std::fs;
+        let path = "test_package_indent.json";
+        fs::write(path, "{\r\n\t\"name\": \"app\",\r\n\t\"version\": \"1.0.0\"\r\n}\r\n").unwrap();
+        let mut config = test_config();
+        config.package_files = Some(vec![super::super::PackageFile {
+            path: path.to_string(),
+            manager: "npm".to_string(),
+            field: None,
+            pattern: None,
+            dependency: None,
+            version_format: None,
+        }]);
+        let result = config.update_package_files("1.1.0");
+        let updated = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert!(result.is_ok());
+        assert_eq!(updated, "{\r\n\t\"name\": \"app\",\r\n\t\"version\": \"1.1.0\"\r\n}\r\n");
+    }
+
+    #[test]
+    fn test_update_json_nested_field() {
+        let config = test_config();
+        let updated = config.update_json_file(r#"{"project":{"version":"1.0.0"}}"#, "2.0.0", "project.version").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&updated).unwrap();
+        assert_eq!(json["project"]["version"], "2.0.0");
+        assert!(json.get("version").is_none());
+    }
+
+    #[test]
+    fn test_update_json_creates_intermediate_objects() {
+        let config = test_config();
+        let updated = config.update_json_file(r#"{"name":"app"}"#, "2.0.0", "info.version").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&updated).unwrap();
+        assert_eq!(json["info"]["version"], "2.0.0");
+    }
+
+    #[test]
+    fn test_update_helm_chart() {
+        let config = test_config();
+        let chart = "apiVersion: v2\nname: my-chart\nversion: 0.1.0\nappVersion: \"1.0.0\"\n";
+        let updated = config.update_yaml_file(chart, "0.2.0", "version").unwrap();
+        let updated = config.update_yaml_file(&updated, "1.1.0", "appVersion").unwrap();
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&updated).unwrap();
+        assert_eq!(yaml["version"].as_str(), Some("0.2.0"));
+        assert_eq!(yaml["appVersion"].as_str(), Some("1.1.0"));
+        assert_eq!(yaml["name"].as_str(), Some("my-chart"));
+        assert!(updated.starts_with("apiVersion: v2\nname: my-chart\n"));
+    }
+
+    #[test]
+    fn test_update_regex_file() {
+        let config = test_config();
+        let content = "#!/bin/sh\nVERSION=\"1.2.3\"\necho $VERSION\n";
+        let updated = config.update_regex_file(content, "1.3.0", r#"VERSION="([^"]*)""#).unwrap();
+        assert_eq!(updated, "#!/bin/sh\nVERSION=\"1.3.0\"\necho $VERSION\n");
+        assert!(config.update_regex_file(content, "1.3.0", r#"RELEASE="([^"]*)""#).is_err());
+    }
+
+    #[test]
+    fn test_update_toml_nested_field() {
+        let config = test_config();
+        let updated = config.update_toml_file("[package]\nname = \"app\"\nversion = \"1.0.0\"\n", "1.2.0", "package.version").unwrap();
+        let toml: toml::Value = toml::from_str(&updated).unwrap();
+        assert_eq!(toml["package"]["version"].as_str(), Some("1.2.0"));
+        assert_eq!(toml["package"]["name"].as_str(), Some("app"));
+    }
+
+    #[test]
+    fn test_update_pyproject_file() {
+        let config = test_config();
+        let content = "[project]\nname = \"app\"\nversion = \"1.0.0\"\n";
+        let updated = config.update_pyproject_file(content, "1.1.0", None).unwrap();
+        let toml: toml::Value = toml::from_str(&updated).unwrap();
+        assert_eq!(toml["project"]["version"].as_str(), Some("1.1.0"));
+
+        let poetry = "[tool.poetry]\nname = \"app\"\nversion = \"1.0.0\"\n";
+        let updated = config.update_pyproject_file(poetry, "1.1.0", None).unwrap();
+        let toml: toml::Value = toml::from_str(&updated).unwrap();
+        assert_eq!(toml["tool"]["poetry"]["version"].as_str(), Some("1.1.0"));
+        assert!(toml.get("project").is_none());
+    }
+
+    #[test]
+    fn test_update_setup_cfg_file() {
+        let config = test_config();
+        let content = "[options]\nversion = 9.9.9\n\n[metadata]\n# the release\nname = app\nversion = 1.0.0\n";
+        let updated = config.update_setup_cfg_file(content, "1.1.0", "metadata.version").unwrap();
+        assert_eq!(updated, "[options]\nversion = 9.9.9\n\n[metadata]\n# the release\nname = app\nversion = 1.1.0\n");
+        assert!(config.update_setup_cfg_file("[metadata]\nname = app\n", "1.1.0", "metadata.version").is_err());
+    }
+
+    #[test]
+    fn test_update_setup_py_file() {
+        let config = test_config();
+        let content = "setup(\n    name='app',\n    version='1.0.0',\n    python_requires=\">=3.8\",\n)\n";
+        let updated = config.update_setup_py_file(content, "1.1.0", "version").unwrap();
+        assert_eq!(updated, "setup(\n    name='app',\n    version='1.1.0',\n    python_requires=\">=3.8\",\n)\n");
+    }
+
+    #[test]
+    fn test_update_package_files_glob() {
+        use std::fs;
+        let root = "test_package_glob";
+        fs::create_dir_all(format!("{}/packages/a", root)).unwrap();
+        fs::create_dir_all(format!("{}/packages/b", root)).unwrap();
+        fs::write(format!("{}/packages/a/package.json", root), r#"{"version": "1.0.0"}"#).unwrap();
+        fs::write(format!("{}/packages/b/package.json", root), r#"{"version": "1.0.0"}"#).unwrap();
+
+        let mut config = test_config();
+        config.package_files = Some(vec![super::super::PackageFile {
+            path: format!("{}/packages/*/package.json", root),
+            manager: "npm".to_string(),
+            field: None,
+            pattern: None,
+            dependency: None,
+            version_format: None,
+        }]);
+        let result = config.update_package_files("1.1.0");
+        let a = fs::read_to_string(format!("{}/packages/a/package.json", root)).unwrap();
+        let b = fs::read_to_string(format!("{}/packages/b/package.json", root)).unwrap();
+        fs::remove_dir_all(root).unwrap();
+
+        assert!(result.is_ok());
+        assert!(a.contains("\"1.1.0\""));
+        assert!(b.contains("\"1.1.0\""));
+    }
+
+    #[test]
+    fn test_update_json_dependency() {
+        let config = test_config();
+        let content = r#"{"name": "b", "version": "2.0.0", "dependencies": {"@scope/a": "^1.0.0"}, "devDependencies": {"@scope/a": "1.0.0"}}"#;
+        let updated = config.update_json_dependency(content, "1.1.0", "@scope/a").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&updated).unwrap();
+        assert_eq!(json["dependencies"]["@scope/a"], "^1.1.0");
+        assert_eq!(json["devDependencies"]["@scope/a"], "1.1.0");
+        assert_eq!(json["version"], "2.0.0");
+        assert!(config.update_json_dependency(content, "1.1.0", "other").is_err());
+    }
+
+    #[test]
+    fn test_cargo_version_field() {
+        let package: toml::Value = toml::from_str("[package]\nname = \"app\"\nversion = \"1.2.3\"\n").unwrap();
+        let inherited: toml::Value = toml::from_str("[package]\nversion.workspace = true\n\n[workspace.package]\nversion = \"2.0.0\"\n").unwrap();
+        assert_eq!(super::cargo_version_field(&package), "package.version");
+        assert_eq!(super::cargo_version_field(&inherited), "workspace.package.version");
+        assert_eq!(super::toml_field(&inherited, "workspace.package.version").and_then(|v| v.as_str()), Some("2.0.0"));
+    }
+
+    #[test]
+    fn test_read_source_version() {
+        let root = "test_read_source_version";
+        std::fs::create_dir_all(root).unwrap();
+        std::fs::write(format!("{}/package.json", root), r#"{"name":"app","version":"1.2.3"}"#).unwrap();
+        std::fs::write(format!("{}/pyproject.toml", root), "[tool.poetry]\nname = \"app\"\nversion = \"0.4.0\"\n").unwrap();
+        std::fs::write(format!("{}/CMakeLists.txt", root), "project(app VERSION 2.1.0)\n").unwrap();
+        let mut config = test_config();
+        config.base_path = Some(root.into());
+        let source = |path: &str, manager: &str, pattern: Option<&str>| super::super::PackageFile {
+            path: path.to_string(),
+            manager: manager.to_string(),
+            field: None,
+            pattern: pattern.map(|p| p.to_string()),
+            dependency: None,
+            version_format: None,
+        };
+        let npm = config.read_source_version(&source("package.json", "npm", None));
+        let poetry = config.read_source_version(&source("pyproject.toml", "python", None));
+        let cmake = source("CMakeLists.txt", "regex", Some(r"VERSION (\S+)\)"));
+        let cmake_version = config.read_source_version(&cmake);
+        config.write_source_version(&cmake, "2.2.0").unwrap();
+        let cmake_bumped = config.read_source_version(&cmake);
+        let mut no_field = source("package.json", "npm", None);
+        no_field.field = Some("engines.node".to_string());
+        let missing = config.read_source_version(&no_field);
+        std::fs::remove_dir_all(root).unwrap();
+
+        assert_eq!(npm.unwrap(), "1.2.3");
+        assert_eq!(poetry.unwrap(), "0.4.0");
+        assert_eq!(cmake_version.unwrap(), "2.1.0");
+        assert_eq!(cmake_bumped.unwrap(), "2.2.0");
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_update_toml_dependency() {
+        let config = test_config();
+        let content = "[package]\nname = \"b\"\nversion = \"2.0.0\"\n\n[dependencies]\na = { version = \"=1.0.0\", path = \"../a\" }\n\n[dev-dependencies]\na = \"1.0\"\n";
+        let updated = config.update_toml_dependency(content, "1.1.0", "a").unwrap();
+        let toml: toml::Value = toml::from_str(&updated).unwrap();
+        assert_eq!(toml["dependencies"]["a"]["version"].as_str(), Some("=1.1.0"));
+        assert_eq!(toml["dependencies"]["a"]["path"].as_str(), Some("../a"));
+        assert_eq!(toml["dev-dependencies"]["a"].as_str(), Some("1.1.0"));
+        assert_eq!(toml["package"]["version"].as_str(), Some("2.0.0"));
+    }
+
+    #[test]
+    fn test_update_npm_lockfile() {
+        let config = test_config();
+        let content = "{\n  \"name\": \"app\",\n  \"version\": \"1.0.0\",\n  \"lockfileVersion\": 3,\n  \"packages\": {\n    \"\": {\n      \"name\": \"app\",\n      \"version\": \"1.0.0\"\n    },\n    \"node_modules/dep\": {\n      \"version\": \"4.0.0\"\n    }\n  }\n}\n";
+        let updated = config.update_npm_lockfile(content, "1.1.0").unwrap();
+        assert_eq!(updated, content.replacen("1.0.0", "1.1.0", 2));
+    }
+}
@@ -1,21 +1,134 @@
-use handlebars::Handlebars;
+use handlebars::{Context, Handlebars, Helper, HelperResult, Output, RenderContext};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use serde_json;
 use chrono::{DateTime, Utc};
 use toml;
+use crate::error::VersionItError;
+use crate::utils::{normalize_line_endings, write_checksum, write_if_changed};
+
+/// Source file extensions counted towards lines of code, by language.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("rs", "Rust"),
+    ("js", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("py", "Python"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cpp", "C++"),
+    ("hpp", "C++"),
+    ("go", "Go"),
+    ("java", "Java"),
+];
+
+/// Built-in header template for the `rust` preset. It only uses values that change
+/// with the version, so the generated file stays untouched between releases.
+pub const RUST_HEADER_TEMPLATE: &str = r#"// Generated by version-it. Do not edit.
+
+pub const VERSION: &str = {{rust-string version}};
+pub const SCHEME: &str = {{rust-string scheme}};
+pub const CHANNEL: &str = {{rust-string channel}};
+"#;
+
+/// Header preset that writes a JSON version manifest instead of rendering a template.
+pub const JSON_PRESET: &str = "json";
+
+/// Names of the built-in header presets.
+pub const HEADER_PRESETS: &[&str] = &["rust", JSON_PRESET];
+
+/// Looks up the template of a built-in header preset.
+///
+/// # Arguments
+///
+/// * `name` - The preset name, e.g. `rust`.
+///
+/// # Returns
+///
+/// The template, or None for an unknown preset and for `json`, which has no template.
+pub fn header_preset(name: &str) -> Option<&'static str> {
+    match name {
+        "rust" => Some(RUST_HEADER_TEMPLATE),
+        _ => None,
+    }
+}
+
+/// Handlebars helper that writes its argument as a quoted Rust string literal, e.g.
+/// `{{rust-string version}}` renders `"1.2.3"`. Quotes, backslashes and control
+/// characters are escaped, and the output bypasses HTML escaping.
+fn rust_string_helper(h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> HelperResult {
+    let value = match h.param(0).map(|p| p.value()) {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    };
+    // str's Debug output is a valid Rust string literal
+    out.write(&format!("{:?}", value))?;
+    Ok(())
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+struct LanguageStats {
+    files: u64,
+    lines: u64,
+    bytes: u64,
+}
+
+/// Sums the size of all files and the per-language file, line and byte counts,
+/// spreading the reads over one thread per CPU.
+fn count_files(files: &[PathBuf]) -> (u64, BTreeMap<&'static str, LanguageStats>) {
+    let chunk_size = files.len().div_ceil(num_cpus::get().max(1)).max(1);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = files
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || {
+                let mut bytes = 0;
+                let mut languages: BTreeMap<&'static str, LanguageStats> = BTreeMap::new();
+                for path in chunk {
+                    bytes += std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+                    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+                    if let Some((_, language)) = LANGUAGES.iter().find(|(ext, _)| *ext == extension) {
+                        if let Ok(content) = std::fs::read_to_string(path) {
+                            let stats = languages.entry(language).or_default();
+                            stats.files += 1;
+                            stats.lines += content.lines().count() as u64;
+                            stats.bytes += content.len() as u64;
+                        }
+                    }
+                }
+                (bytes, languages)
+            }))
+            .collect();
+
+        let mut total_bytes = 0;
+        let mut totals: BTreeMap<&'static str, LanguageStats> = BTreeMap::new();
+        for worker in workers {
+            let (bytes, languages) = worker.join().unwrap_or_default();
+            total_bytes += bytes;
+            for (language, stats) in languages {
+                let total = totals.entry(language).or_default();
+                total.files += stats.files;
+                total.lines += stats.lines;
+                total.bytes += stats.bytes;
+            }
+        }
+        (total_bytes, totals)
+    })
+}
 
 impl super::Config {
     fn current_datetime() -> String {
-        let now: DateTime<Utc> = Utc::now();
+        let now: DateTime<Utc> = crate::utils::now();
         now.format("%Y-%m-%dT%H:%M:%S").to_string()
     }
 
     fn build_date() -> String {
-        let now: DateTime<Utc> = Utc::now();
+        let now: DateTime<Utc> = crate::utils::now();
         now.format("%Y-%m-%d").to_string()
     }
 
     fn build_time() -> String {
-        let now: DateTime<Utc> = Utc::now();
+        let now: DateTime<Utc> = crate::utils::now();
         now.format("%H:%M:%S").to_string()
     }
 
@@ -58,6 +171,20 @@ impl super::Config {
         })
     }
 
+    /// Lists the files counted by the project stats, honouring `.gitignore` and
+    /// skipping the `.git` directory so build artifacts don't skew the numbers.
+    fn project_files(root: &std::path::Path) -> Vec<PathBuf> {
+        ignore::WalkBuilder::new(root)
+            .hidden(false)
+            .require_git(false)
+            .filter_entry(|e| e.file_name() != ".git")
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+            .map(|e| e.into_path())
+            .collect()
+    }
+
     fn gather_stats(&self) -> serde_json::Value {
         // Check for cached stats first
         let cache_file = ".version-it-stats-cache.json";
@@ -71,6 +198,7 @@ impl super::Config {
                             .unwrap_or_default()
                             .as_secs();
                         if now - timestamp < 3600 { // 1 hour cache
+                            log::debug!("Using cached project statistics from {}", cache_file);
                             return cache;
                         }
                     }
@@ -79,28 +207,12 @@ impl super::Config {
         }
 
         // Calculate stats (expensive operation)
-        println!("Calculating project statistics... (this may take a moment)");
-        let file_count = walkdir::WalkDir::new(".")
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-            .count();
-
+        log::info!("Calculating project statistics... (this may take a moment)");
+        let files = Self::project_files(std::path::Path::new("."));
+        let file_count = files.len();
+        let (bytes, languages) = count_files(&files);
         // Approximate lines of code (very basic)
-        let mut lines_of_code = 0;
-        walkdir::WalkDir::new(".")
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-            .filter(|e| {
-                let path = e.path().to_string_lossy();
-                path.ends_with(".rs") || path.ends_with(".js") || path.ends_with(".ts") || path.ends_with(".py")
-            })
-            .for_each(|e| {
-                if let Ok(content) = std::fs::read_to_string(e.path()) {
-                    lines_of_code += content.lines().count();
-                }
-            });
+        let lines_of_code: u64 = languages.values().map(|l| l.lines).sum();
 
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -110,6 +222,8 @@ impl super::Config {
         let stats = serde_json::json!({
             "file_count": file_count,
             "lines_of_code": lines_of_code,
+            "bytes": bytes,
+            "languages": languages,
             "timestamp": timestamp
         });
 
@@ -119,7 +233,42 @@ impl super::Config {
         stats
     }
 
-    /// Generates version header files based on the configuration.
+    /// Reads the handlebars template of a version header.
+    fn header_template(&self, header: &super::VersionHeader) -> Result<String, VersionItError> {
+        if let Some(ref template_path) = header.template_path {
+            Ok(std::fs::read_to_string(self.resolve_path(template_path))?)
+        } else if let Some(ref template) = header.template {
+            Ok(template.clone())
+        } else if let Some(ref preset) = header.preset {
+            header_preset(preset)
+                .map(str::to_string)
+                .ok_or_else(|| VersionItError::Template(format!("Unknown header preset '{}'", preset)))
+        } else {
+            Err(VersionItError::Template("Either template, template-path or preset must be specified for version header".to_string()))
+        }
+    }
+
+    /// Builds the `json` preset manifest from the header template data: the version,
+    /// scheme, channel (null without one), git commit and branch, and build timestamp.
+    fn version_manifest(data: &serde_json::Value, channel: Option<&str>) -> Result<String, VersionItError> {
+        let manifest = serde_json::json!({
+            "version": data["version"],
+            "scheme": data["scheme"],
+            "channel": channel,
+            "git": {
+                "commit": data["git"]["commit_hash_full"],
+                "branch": data["git"]["branch"]
+            },
+            "build": {
+                "timestamp": data["build"]["timestamp"]
+            }
+        });
+        let json = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| VersionItError::Template(format!("Failed to serialize version manifest: {}", e)))?;
+        Ok(json + "\n")
+    }
+
+    /// Renders the version headers without writing them.
     ///
     /// # Arguments
     ///
@@ -128,18 +277,14 @@ impl super::Config {
     ///
     /// # Returns
     ///
-    /// A Result indicating success or failure.
-    pub fn generate_headers(&self, version: &str, channel: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    /// A Result containing the configured path and rendered content of each header.
+    pub fn render_headers(&self, version: &str, channel: Option<&str>) -> Result<Vec<(String, String)>, VersionItError> {
+        let mut rendered = Vec::new();
         if let Some(headers) = &self.version_headers {
-            let handlebars = Handlebars::new();
+            let mut handlebars = Handlebars::new();
+            handlebars.register_helper("rust-string", Box::new(rust_string_helper));
+            let mut changelog: Option<(String, serde_json::Value)> = None;
             for header in headers {
-                let template = if let Some(ref template_path) = header.template_path {
-                    std::fs::read_to_string(template_path)?
-                } else if let Some(ref template) = header.template {
-                    template.clone()
-                } else {
-                    return Err("Either template or template-path must be specified for version header".into());
-                };
                 let git_info = Self::gather_git_info();
                 let project_info = Self::gather_project_info();
                 let stats_info = if self.enable_expensive_metrics {
@@ -167,15 +312,335 @@ impl super::Config {
                         "os": super::VersionInfo::os_info(),
                         "arch": super::VersionInfo::arch_info(),
                         "cpus": super::VersionInfo::cpu_count(),
-                        "memory": super::VersionInfo::available_memory()
+                        "memory": super::VersionInfo::available_memory_human(),
+                        "memory_bytes": super::VersionInfo::available_memory_bytes()
                     },
                     "project": project_info,
                     "stats": stats_info
                 });
-                let content = handlebars.render_template(&template, &data)?;
-                std::fs::write(&header.path, content)?;
+                let content = if header.preset.as_deref() == Some(JSON_PRESET) {
+                    Self::version_manifest(&data, channel)?
+                } else {
+                    let template = self.header_template(header)?;
+                    let mut data = data;
+                    // Only build the changelog, which walks the git log, for templates using it
+                    if !self.changelog_exporters.is_empty() && template.contains("changelog") {
+                        if changelog.is_none() {
+                            let built = self.build_changelog(version)?;
+                            changelog = Some((self.render_changelog(&built)?, serde_json::to_value(&built.sections)?));
+                        }
+                        if let Some((ref text, ref sections)) = changelog {
+                            data["changelog"] = serde_json::json!(text);
+                            data["changelog_sections"] = sections.clone();
+                        }
+                    }
+                    handlebars.render_template(&template, &data)?
+                };
+                let content = match self.line_ending {
+                    Some(line_ending) => normalize_line_endings(&content, line_ending == super::LineEnding::Crlf),
+                    None => content,
+                };
+                log::debug!("Rendered header {}", header.path);
+                rendered.push((header.path.clone(), content));
+            }
+        }
+        Ok(rendered)
+    }
+
+    /// Generates version header files based on the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The version string to include in the headers.
+    /// * `channel` - Optional channel name to include in the headers.
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or failure.
+    pub fn generate_headers(&self, version: &str, channel: Option<&str>) -> Result<(), VersionItError> {
+        for (path, content) in self.render_headers(version, channel)? {
+            // Unchanged headers are left alone so dependent sources don't rebuild
+            write_if_changed(self.resolve_path(&path), content)?;
+            if self.emit_checksum {
+                write_checksum(self.resolve_path(&path))?;
             }
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Renders a one-line output format such as `v{{version}}` with handlebars.
+///
+/// # Arguments
+///
+/// * `format` - The handlebars template to render.
+/// * `version` - The new version.
+/// * `previous_version` - The version before bumping.
+/// * `scheme` - The versioning scheme.
+/// * `channel` - Optional release channel.
+///
+/// # Returns
+///
+/// The rendered string, or an error if the template is invalid.
+pub fn render_version_format(format: &str, version: &str, previous_version: &str, scheme: &str, channel: Option<&str>) -> Result<String, VersionItError> {
+    let mut handlebars = Handlebars::new();
+    // Output goes to a terminal or script, not HTML
+    handlebars.register_escape_fn(handlebars::no_escape);
+    let data = serde_json::json!({
+        "version": version,
+        "previous_version": previous_version,
+        "scheme": scheme,
+        "channel": channel.unwrap_or("")
+    });
+    Ok(handlebars.render_template(format, &data)?)
+}
+
+/// Renders a package file's `version-format`, such as `{{major}},{{minor}},{{patch}},0`.
+///
+/// `major`, `minor` and `patch` are the leading dot-separated numbers of the version
+/// (empty when missing), `pre` is what follows the first `-` and `build` what follows
+/// the `+`, so `2024.01.15` and `1.2.3-rc.1+7` both split as expected.
+///
+/// # Arguments
+///
+/// * `format` - The handlebars template to render.
+/// * `version` - The new version.
+///
+/// # Returns
+///
+/// The rendered version, or an error if the template is invalid.
+pub fn render_package_version(format: &str, version: &str) -> Result<String, VersionItError> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    let (rest, build) = version.split_once('+').unwrap_or((version, ""));
+    let (core, pre) = rest.split_once('-').unwrap_or((rest, ""));
+    let mut components = core.split('.').map(|c| c.parse::<u64>().ok());
+    let mut next = || components.next().flatten();
+    let data = serde_json::json!({
+        "version": version,
+        "major": next(),
+        "minor": next(),
+        "patch": next(),
+        "pre": pre,
+        "build": build
+    });
+    Ok(handlebars.render_template(format, &data)?)
+}
+
+/// Renders a handlebars tag annotation such as `Release {{version}}\n\n{{changelog}}`.
+///
+/// # Arguments
+///
+/// * `template` - The handlebars template to render.
+/// * `version` - The new version.
+/// * `previous_version` - The version before bumping.
+/// * `scheme` - The versioning scheme.
+/// * `channel` - Optional release channel.
+/// * `changelog` - The rendered changelog entry for the release, or empty.
+///
+/// # Returns
+///
+/// The rendered message, or an error if the template is invalid.
+pub fn render_tag_message(template: &str, version: &str, previous_version: &str, scheme: &str, channel: Option<&str>, changelog: &str) -> Result<String, VersionItError> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    let data = serde_json::json!({
+        "version": version,
+        "previous_version": previous_version,
+        "scheme": scheme,
+        "channel": channel.unwrap_or(""),
+        "changelog": changelog
+    });
+    Ok(handlebars.render_template(template, &data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_version_format() {
+        let rendered = render_version_format("v{{version}}-{{channel}} (was {{previous_version}}, {{scheme}})", "1.3.0", "1.2.0", "semantic", Some("beta")).unwrap();
+        assert_eq!(rendered, "v1.3.0-beta (was 1.2.0, semantic)");
+    }
+
+    #[test]
+    fn test_render_package_version() {
+        assert_eq!(render_package_version("{{major}},{{minor}},{{patch}},0", "1.2.3-rc.1+7").unwrap(), "1,2,3,0");
+        assert_eq!(render_package_version("{{version}}-1", "1.2.3").unwrap(), "1.2.3-1");
+        assert_eq!(render_package_version("{{pre}}/{{build}}", "1.2.3-rc.1+7").unwrap(), "rc.1/7");
+        assert_eq!(render_package_version("{{major}}.{{minor}}.{{patch}}", "42").unwrap(), "42..");
+    }
+
+    #[test]
+    fn test_render_version_format_does_not_escape() {
+        let rendered = render_version_format("'{{version}}'", "1.0.0+build&1", "1.0.0", "semantic", None).unwrap();
+        assert_eq!(rendered, "'1.0.0+build&1'");
+    }
+
+    #[test]
+    fn test_render_tag_message() {
+        let rendered = render_tag_message("Release {{version}}\n\n{{changelog}}", "1.3.0", "1.2.0", "semantic", None, "### Added\n- <feature>").unwrap();
+        assert_eq!(rendered, "Release 1.3.0\n\n### Added\n- <feature>");
+    }
+
+    #[test]
+    fn test_rust_preset_header() {
+        let yaml = r#"
+run-on-branches: [main]
+versioning-scheme: semantic
+first-version: 0.1.0
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+channel: "be\"ta"
+version-headers:
+- path: test_rust_preset_header.rs
+  preset: rust
+"#;
+        let config: super::super::Config = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        config.generate_headers("1.2.3+build&1", config.channel.as_deref()).unwrap();
+        let content = std::fs::read_to_string("test_rust_preset_header.rs").unwrap();
+        std::fs::remove_file("test_rust_preset_header.rs").unwrap();
+
+        assert!(content.contains("pub const VERSION: &str = \"1.2.3+build&1\";"));
+        assert!(content.contains("pub const CHANNEL: &str = \"be\\\"ta\";"));
+    }
+
+    #[test]
+    fn test_json_preset_header() {
+        let yaml = r#"
+run-on-branches: [main]
+versioning-scheme: semantic
+first-version: 0.1.0
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+version-headers:
+- path: test_json_preset_header.json
+  preset: json
+"#;
+        let config: super::super::Config = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        config.generate_headers("1.2.3-\"rc\"", None).unwrap();
+        let content = std::fs::read_to_string("test_json_preset_header.json").unwrap();
+        std::fs::remove_file("test_json_preset_header.json").unwrap();
+
+        let manifest: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(manifest["version"], "1.2.3-\"rc\"");
+        assert_eq!(manifest["scheme"], "semantic");
+        assert!(manifest["channel"].is_null());
+        assert!(manifest["git"]["commit"].is_string());
+        assert!(manifest["git"]["branch"].is_string());
+        assert!(manifest["build"]["timestamp"].is_string());
+    }
+
+    #[test]
+    fn test_header_line_ending() {
+        let yaml = r##"
+run-on-branches: [main]
+versioning-scheme: semantic
+first-version: 0.1.0
+calver-enable-branch: false
+changelog-sections: []
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+line-ending: crlf
+version-headers:
+- path: version.h
+  template: "#define VERSION \"{{version}}\"\n#define CHANNEL \"{{channel}}\"\r\n"
+"##;
+        let mut config: super::super::Config = serde_yaml::from_str(yaml).unwrap();
+        let crlf = config.render_headers("1.2.3", None).unwrap();
+        config.line_ending = Some(super::super::LineEnding::Lf);
+        let lf = config.render_headers("1.2.3", None).unwrap();
+
+        assert_eq!(crlf[0].1, "#define VERSION \"1.2.3\"\r\n#define CHANNEL \"\"\r\n");
+        assert_eq!(lf[0].1, "#define VERSION \"1.2.3\"\n#define CHANNEL \"\"\n");
+    }
+
+    #[test]
+    fn test_header_changelog() {
+        let yaml = r#"
+run-on-branches: [main]
+versioning-scheme: semantic
+first-version: 0.1.0
+calver-enable-branch: false
+changelog-exporters:
+  format: keep-a-changelog
+  output-path: CHANGELOG.md
+changelog-sections:
+  - title: Everything
+    labels: [""]
+change-substitutions: []
+change-type-map: []
+commit-based-bumping: false
+enable-expensive-metrics: false
+version-headers:
+- path: about.txt
+  template: "{{{changelog}}}|{{#each changelog_sections}}{{title}}{{/each}}"
+- path: version.txt
+  template: "{{version}}"
+"#;
+        let mut config: super::super::Config = serde_yaml::from_str(yaml).unwrap();
+        let rendered = config.render_headers("1.2.3", None).unwrap();
+        config.changelog_exporters.clear();
+        let without_exporters = config.render_headers("1.2.3", None).unwrap();
+
+        assert!(rendered[0].1.starts_with("## [1.2.3]"), "{}", rendered[0].1);
+        assert!(rendered[0].1.ends_with("|Everything"), "{}", rendered[0].1);
+        assert_eq!(rendered[1].1, "1.2.3");
+        assert_eq!(without_exporters[0].1, "|");
+    }
+
+    #[test]
+    fn test_count_files_by_language() {
+        use std::fs;
+        let root = std::path::Path::new("test_count_files");
+        fs::create_dir_all(root).unwrap();
+        let files: Vec<PathBuf> = [("a.rs", "fn a() {}\nfn b() {}\n"), ("b.rs", "fn c() {}\n"), ("c.py", "x = 1\n"), ("notes.txt", "hello")]
+            .iter()
+            .map(|(name, content)| {
+                let path = root.join(name);
+                fs::write(&path, content).unwrap();
+                path
+            })
+            .collect();
+
+        let (bytes, languages) = count_files(&files);
+        fs::remove_dir_all(root).unwrap();
+
+        assert_eq!(bytes, 20 + 10 + 6 + 5);
+        assert_eq!(languages["Rust"], LanguageStats { files: 2, lines: 3, bytes: 30 });
+        assert_eq!(languages["Python"], LanguageStats { files: 1, lines: 1, bytes: 6 });
+        assert_eq!(languages.len(), 2);
+    }
+
+    #[test]
+    fn test_project_files_respects_gitignore() {
+        use std::fs;
+        let root = std::path::Path::new("test_project_files");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("target/app"), "binary").unwrap();
+        fs::write(root.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+
+        let mut files = super::super::Config::project_files(root);
+        fs::remove_dir_all(root).unwrap();
+        files.sort();
+
+        assert_eq!(files, vec![root.join(".gitignore"), root.join("src/main.rs")]);
+    }
+}
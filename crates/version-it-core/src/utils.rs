@@ -1 +1,256 @@
-// Utility functions
\ No newline at end of file
+// Utility functions
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Replaces a file's contents atomically.
+///
+/// The contents are written to a temporary file in the same directory, which is then
+/// renamed over the target, so readers never see a half-written file. Permissions of
+/// an existing target are kept.
+///
+/// # Arguments
+///
+/// * `path` - The file to write.
+/// * `contents` - The new contents.
+///
+/// # Returns
+///
+/// A Result indicating success or failure.
+pub fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let tmp_path = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&tmp_path, metadata.permissions())?;
+        }
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Writes a file atomically unless it already has exactly these contents.
+///
+/// Leaving unchanged files alone keeps their mtime, so build systems don't rebuild
+/// everything that includes a generated file.
+///
+/// # Returns
+///
+/// A Result containing whether the file was written.
+pub fn write_if_changed<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> std::io::Result<bool> {
+    if fs::read(path.as_ref()).is_ok_and(|existing| existing == contents.as_ref()) {
+        return Ok(false);
+    }
+    write_atomic(path, contents)?;
+    Ok(true)
+}
+
+/// Returns the path of the checksum file written for a file, `<path>.sha256`.
+pub fn checksum_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut checksum = path.as_ref().as_os_str().to_owned();
+    checksum.push(".sha256");
+    PathBuf::from(checksum)
+}
+
+/// Writes the SHA-256 digest of a file next to it, in `sha256sum` format
+/// (`<hex digest>  <file name>`), so it can be checked with `sha256sum -c`.
+///
+/// # Returns
+///
+/// A Result containing the path of the checksum file.
+pub fn write_checksum<P: AsRef<Path>>(path: P) -> std::io::Result<PathBuf> {
+    use sha2::{Digest, Sha256};
+    let path = path.as_ref();
+    let digest = Sha256::digest(fs::read(path)?);
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let checksum = checksum_path(path);
+    write_if_changed(&checksum, format!("{}  {}\n", hex, file_name))?;
+    Ok(checksum)
+}
+
+/// Turns a branch name into a version-safe slug: lowercase ASCII letters, digits and
+/// single dashes, e.g. `feature/Login_Page` becomes `feature-login-page`.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Returns the indentation unit of a file: the leading whitespace of its first
+/// indented line, e.g. a tab or four spaces. None when no line is indented.
+pub fn detect_indent(content: &str) -> Option<&str> {
+    content.lines().find_map(|line| {
+        let trimmed = line.trim_start_matches([' ', '\t']);
+        let indent = &line[..line.len() - trimmed.len()];
+        (!indent.is_empty() && !trimmed.trim().is_empty()).then_some(indent)
+    })
+}
+
+/// Rewrites all line endings as CRLF (`crlf`) or LF.
+pub fn normalize_line_endings(content: &str, crlf: bool) -> String {
+    let lf = content.replace("\r\n", "\n");
+    if crlf {
+        lf.replace('\n', "\r\n")
+    } else {
+        lf
+    }
+}
+
+/// Returns the current time, or the `SOURCE_DATE_EPOCH` instant when that variable is
+/// set, so timestamps in versions, headers and changelogs are reproducible.
+///
+/// An invalid `SOURCE_DATE_EPOCH` is ignored with a warning.
+pub fn now() -> DateTime<Utc> {
+    source_date_epoch(std::env::var("SOURCE_DATE_EPOCH").ok().as_deref()).unwrap_or_else(Utc::now)
+}
+
+/// Parses a `SOURCE_DATE_EPOCH` value, seconds since the Unix epoch.
+fn source_date_epoch(value: Option<&str>) -> Option<DateTime<Utc>> {
+    let value = value?.trim();
+    let parsed = value.parse::<i64>().ok().and_then(|secs| DateTime::from_timestamp(secs, 0));
+    if parsed.is_none() {
+        log::warn!("Ignoring invalid SOURCE_DATE_EPOCH '{}'", value);
+    }
+    parsed
+}
+
+/// Returns whether a path contains glob metacharacters.
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Expands a glob pattern to the matching paths, sorted.
+///
+/// Literal paths (without glob metacharacters) are returned unchanged, whether they
+/// exist or not.
+///
+/// # Arguments
+///
+/// * `pattern` - The path or glob, e.g. `packages/*/package.json`.
+///
+/// # Returns
+///
+/// A Result containing the matching paths or an error for an invalid pattern.
+pub fn expand_glob(pattern: &Path) -> Result<Vec<PathBuf>, glob::PatternError> {
+    let pattern_str = pattern.to_string_lossy();
+    if !is_glob(&pattern_str) {
+        return Ok(vec![pattern.to_path_buf()]);
+    }
+    let mut paths: Vec<PathBuf> = glob::glob(&pattern_str)?.filter_map(|p| p.ok()).collect();
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let path = "test_write_atomic.txt";
+        fs::write(path, "old contents that are longer").unwrap();
+        write_atomic(path, "new").unwrap();
+        let contents = fs::read_to_string(path).unwrap();
+        let leftover = fs::read_dir(".").unwrap()
+            .filter_map(|e| e.ok())
+            .any(|e| e.file_name().to_string_lossy().starts_with(".test_write_atomic.txt."));
+        fs::remove_file(path).unwrap();
+        assert_eq!(contents, "new");
+        assert!(!leftover);
+    }
+
+    #[test]
+    fn test_write_if_changed_keeps_unchanged_file() {
+        let path = "test_write_if_changed.txt";
+        fs::write(path, "same").unwrap();
+        let unchanged = write_if_changed(path, "same").unwrap();
+        let changed = write_if_changed(path, "different").unwrap();
+        let contents = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert!(!unchanged);
+        assert!(changed);
+        assert_eq!(contents, "different");
+    }
+
+    #[test]
+    fn test_write_checksum() {
+        let path = "test_write_checksum.txt";
+        fs::write(path, "1.2.3").unwrap();
+        let checksum = write_checksum(path).unwrap();
+        let contents = fs::read_to_string(&checksum).unwrap();
+        fs::remove_file(path).unwrap();
+        fs::remove_file(&checksum).unwrap();
+        assert_eq!(checksum, PathBuf::from("test_write_checksum.txt.sha256"));
+        assert_eq!(contents, "c47f5b18b8a430e698b9fe15e51f6119984e78334bcf3f45e210d30c37ef2f9e  test_write_checksum.txt\n");
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("main"), "main");
+        assert_eq!(slugify("feature/Login_Page"), "feature-login-page");
+        assert_eq!(slugify("--fix//#12--"), "fix-12");
+    }
+
+    #[test]
+    fn test_detect_indent() {
+        assert_eq!(detect_indent("{\n\t\"a\": 1\n}"), Some("\t"));
+        assert_eq!(detect_indent("{\n\n    \"a\": {\n        \"b\": 1\n    }\n}"), Some("    "));
+        assert_eq!(detect_indent("{\"a\": 1}"), None);
+    }
+
+    #[test]
+    fn test_normalize_line_endings() {
+        assert_eq!(normalize_line_endings("a\nb\r\n", true), "a\r\nb\r\n");
+        assert_eq!(normalize_line_endings("a\r\nb\n", false), "a\nb\n");
+    }
+
+    #[test]
+    fn test_source_date_epoch() {
+        assert_eq!(source_date_epoch(Some("1700000000")).unwrap().to_rfc3339(), "2023-11-14T22:13:20+00:00");
+        assert!(source_date_epoch(Some("yesterday")).is_none());
+        assert!(source_date_epoch(None).is_none());
+    }
+
+    #[test]
+    fn test_write_atomic_missing_directory() {
+        assert!(write_atomic("no-such-dir/test_write_atomic.txt", "new").is_err());
+    }
+
+    #[test]
+    fn test_expand_glob() {
+        let root = "test_expand_glob";
+        fs::create_dir_all(format!("{}/a", root)).unwrap();
+        fs::create_dir_all(format!("{}/b", root)).unwrap();
+        fs::write(format!("{}/a/package.json", root), "{}").unwrap();
+        fs::write(format!("{}/b/package.json", root), "{}").unwrap();
+        let matched = expand_glob(Path::new(&format!("{}/*/package.json", root))).unwrap();
+        let literal = expand_glob(Path::new("missing/package.json")).unwrap();
+        fs::remove_dir_all(root).unwrap();
+
+        assert_eq!(matched, vec![
+            PathBuf::from(format!("{}/a/package.json", root)),
+            PathBuf::from(format!("{}/b/package.json", root)),
+        ]);
+        assert_eq!(literal, vec![PathBuf::from("missing/package.json")]);
+    }
+}
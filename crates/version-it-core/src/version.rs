@@ -1,26 +1,304 @@
 use semver::{Version, Prerelease, BuildMetadata};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc, Weekday};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::process::Command;
 use std::fmt;
+use std::str::FromStr;
+use crate::error::VersionItError;
+
+/// A versioning scheme, as named by `versioning-scheme` in the config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scheme {
+    #[default]
+    Semantic,
+    Calver,
+    Timestamp,
+    Commit,
+    Build,
+    /// Any number of dot-separated numbers, e.g. `3.1` or `1.0.4.2.7`.
+    Numeric,
+    Monotonic,
+    Datetime,
+    Pattern,
+    SemanticCommit,
+}
+
+impl Scheme {
+    /// Every scheme, in documentation order.
+    pub const ALL: [Scheme; 10] = [
+        Scheme::Semantic,
+        Scheme::Calver,
+        Scheme::Timestamp,
+        Scheme::Commit,
+        Scheme::Build,
+        Scheme::Numeric,
+        Scheme::Monotonic,
+        Scheme::Datetime,
+        Scheme::Pattern,
+        Scheme::SemanticCommit,
+    ];
+
+    /// Returns the config name of the scheme, e.g. `semantic-commit`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scheme::Semantic => "semantic",
+            Scheme::Calver => "calver",
+            Scheme::Timestamp => "timestamp",
+            Scheme::Commit => "commit",
+            Scheme::Build => "build",
+            Scheme::Numeric => "numeric",
+            Scheme::Monotonic => "monotonic",
+            Scheme::Datetime => "datetime",
+            Scheme::Pattern => "pattern",
+            Scheme::SemanticCommit => "semantic-commit",
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scheme {
+    type Err = VersionItError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Scheme::ALL.into_iter().find(|scheme| scheme.as_str() == name).ok_or_else(|| {
+            let names: Vec<&str> = Scheme::ALL.iter().map(|s| s.as_str()).collect();
+            VersionItError::VersionParse(format!("Unknown versioning scheme '{}'. Use one of: {}", name, names.join(", ")))
+        })
+    }
+}
+
+/// Layout of calver versions, set with `calver-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum CalverFormat {
+    /// Year, month and day, e.g. `25.10.01`.
+    #[default]
+    #[serde(rename = "YY.MM.DD")]
+    YearMonthDay,
+    /// Year and ISO 8601 week, e.g. `2025.41`.
+    #[serde(rename = "YYYY.WW")]
+    IsoWeek,
+}
+
+impl CalverFormat {
+    /// Every calver format, in the order they are documented.
+    pub const ALL: [CalverFormat; 2] = [CalverFormat::YearMonthDay, CalverFormat::IsoWeek];
+
+    /// Returns the config name of the format, e.g. `YYYY.WW`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CalverFormat::YearMonthDay => "YY.MM.DD",
+            CalverFormat::IsoWeek => "YYYY.WW",
+        }
+    }
+}
+
+/// A placeholder or literal text of a pattern scheme template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternToken {
+    Literal(String),
+    Major,
+    Minor,
+    Patch,
+    Build,
+    /// The current UTC date, in a chrono strftime format.
+    Date(String),
+    Commit,
+}
+
+/// A version of the pattern scheme: a template such as `{major}.{minor}.{date:%Y%m%d}`
+/// and the values of its placeholders.
+///
+/// `{major}`, `{minor}`, `{patch}` and `{build}` are counters read back from the
+/// current version; `{date}` / `{date:FORMAT}` and `{commit}` are filled in when the
+/// version is bumped. `{{` and `}}` are literal braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternVersion {
+    pub template: String,
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Incremented on every bump and never reset.
+    pub build: u64,
+    /// The version as last rendered, so dates and commits only change on bump.
+    pub rendered: String,
+}
+
+impl PatternVersion {
+    /// Reads the placeholder values of a version rendered from `template`.
+    ///
+    /// An empty version starts with every counter at zero.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the PatternVersion, or an error for an invalid template or
+    /// a version that does not match it.
+    pub fn parse(template: &str, version: &str) -> Result<Self, VersionItError> {
+        let tokens = Self::tokens(template)?;
+        let mut pattern = Self { template: template.to_string(), major: 0, minor: 0, patch: 0, build: 0, rendered: String::new() };
+        if version.is_empty() {
+            pattern.render_tokens(&tokens);
+            return Ok(pattern);
+        }
+
+        let mut regex_str = String::from("^");
+        for token in &tokens {
+            regex_str.push_str(&match token {
+                PatternToken::Literal(text) => regex::escape(text),
+                PatternToken::Date(_) => "(.+?)".to_string(),
+                PatternToken::Commit => "([0-9a-f]+|unknown)".to_string(),
+                _ => r"(\d+)".to_string(),
+            });
+        }
+        regex_str.push('$');
+        let captures = regex::Regex::new(&regex_str)?.captures(version)
+            .ok_or_else(|| VersionItError::VersionParse(format!("Version '{}' does not match pattern '{}'", version, template)))?;
+        let groups = tokens.iter().filter(|t| !matches!(t, PatternToken::Literal(_)));
+        for (token, group) in groups.zip(captures.iter().skip(1)) {
+            let value = group.map(|m| m.as_str()).unwrap_or_default();
+            match token {
+                PatternToken::Major => pattern.major = value.parse()?,
+                PatternToken::Minor => pattern.minor = value.parse()?,
+                PatternToken::Patch => pattern.patch = value.parse()?,
+                PatternToken::Build => pattern.build = value.parse()?,
+                _ => {}
+            }
+        }
+        pattern.rendered = version.to_string();
+        Ok(pattern)
+    }
+
+    /// Derives a template from a version without one: the first three numbers become
+    /// `{major}`, `{minor}` and `{patch}`, so `v1.0.0-snapshot` bumps to `v1.1.0-snapshot`.
+    /// Numbers too large for a counter stay literal text.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the PatternVersion, or an error if the version can't be read
+    /// back with the derived template.
+    pub fn infer(version: &str) -> Result<Self, VersionItError> {
+        let escaped = version.replace('{', "{{").replace('}', "}}");
+        let mut placeholders = ["{major}", "{minor}", "{patch}"].into_iter();
+        let template = regex::Regex::new(r"\d+")?
+            .replace_all(&escaped, |caps: &regex::Captures| {
+                let counter = caps[0].parse::<u64>().ok().and_then(|_| placeholders.next());
+                counter.map(|p| p.to_string()).unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned();
+        Self::parse(&template, version)
+    }
+
+    /// Checks that a template only uses known placeholders and valid date formats.
+    pub fn validate_template(template: &str) -> Result<(), VersionItError> {
+        Self::tokens(template).map(|_| ())
+    }
+
+    /// Splits a template into literal text and placeholders.
+    fn tokens(template: &str) -> Result<Vec<PatternToken>, VersionItError> {
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    let token = match name.split_once(':') {
+                        Some(("date", format)) => {
+                            if chrono::format::StrftimeItems::new(format).any(|item| matches!(item, chrono::format::Item::Error)) {
+                                return Err(VersionItError::VersionParse(format!("Invalid date format '{}' in pattern '{}'", format, template)));
+                            }
+                            PatternToken::Date(format.to_string())
+                        }
+                        _ => match name.as_str() {
+                            "major" => PatternToken::Major,
+                            "minor" => PatternToken::Minor,
+                            "patch" => PatternToken::Patch,
+                            "build" => PatternToken::Build,
+                            "date" => PatternToken::Date("%Y%m%d".to_string()),
+                            "commit" => PatternToken::Commit,
+                            _ => return Err(VersionItError::VersionParse(format!("Unknown placeholder '{{{}}}' in pattern '{}'", name, template))),
+                        },
+                    };
+                    if !literal.is_empty() {
+                        tokens.push(PatternToken::Literal(std::mem::take(&mut literal)));
+                    }
+                    tokens.push(token);
+                }
+                '}' => return Err(VersionItError::VersionParse(format!("Unmatched '}}' in pattern '{}'", template))),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            tokens.push(PatternToken::Literal(literal));
+        }
+        Ok(tokens)
+    }
+
+    fn render_tokens(&mut self, tokens: &[PatternToken]) {
+        let now = crate::utils::now();
+        self.rendered = tokens.iter().map(|token| match token {
+            PatternToken::Literal(text) => text.clone(),
+            PatternToken::Major => self.major.to_string(),
+            PatternToken::Minor => self.minor.to_string(),
+            PatternToken::Patch => self.patch.to_string(),
+            PatternToken::Build => self.build.to_string(),
+            PatternToken::Date(format) => now.format(format).to_string(),
+            PatternToken::Commit => VersionInfo::current_commit().unwrap_or_else(|_| "unknown".to_string()),
+        }).collect();
+    }
+
+    /// Renders the template with the current counters, date and commit.
+    fn render(&mut self) {
+        // The template was validated when the version was parsed
+        if let Ok(tokens) = Self::tokens(&self.template) {
+            self.render_tokens(&tokens);
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum VersionType {
     Semantic(Version),
     Calver { year: u32, month: u32, day: u32 },
+    /// Calver with an ISO 8601 week-numbering year and week.
+    CalverWeek { year: i32, week: u32 },
     Timestamp(String),
     Commit(String),
     Build { major: u32, minor: u32, patch: u32, build: u32 },
+    /// Dot-separated numbers; major, minor and patch are the first three.
+    Numeric(Vec<u64>),
     Monotonic(u64),
     Datetime(String),
-    Pattern(String),
+    Pattern(PatternVersion),
     SemanticCommit { major: u32, minor: u32, commit_count: u32 },
 }
 
 #[derive(Debug, Clone)]
 pub struct VersionInfo {
-    pub scheme: String,
+    pub scheme: Scheme,
     pub version: VersionType,
     pub channel: Option<String>,
+    /// Increment applied to monotonic versions on every bump.
+    pub monotonic_step: u64,
+    /// Separator between the version and the channel suffix (default `-`).
+    pub channel_separator: String,
+    /// Branch appended to calver versions, e.g. `25.10.01-main` (`calver-enable-branch`).
+    pub branch: Option<String>,
 }
 
 impl VersionInfo {
@@ -29,42 +307,44 @@ impl VersionInfo {
     /// # Arguments
     ///
     /// * `version` - The version string to parse.
-    /// * `scheme` - The versioning scheme: "semantic", "calver", "timestamp", or "commit".
+    /// * `scheme` - The versioning scheme.
     /// * `channel` - Optional channel name (stable, beta, nightly, etc.)
     ///
     /// # Returns
     ///
     /// A Result containing the VersionInfo or an error if parsing fails.
-    pub fn new(version: &str, scheme: &str, channel: Option<String>) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(version: &str, scheme: Scheme, channel: Option<String>) -> Result<Self, VersionItError> {
         let version_type = match scheme {
-            "calver" => {
+            Scheme::Calver => {
+                // Drop a branch or channel suffix such as `-main`
+                let version = version.split_once('-').map_or(version, |(date, _)| date);
                 let parts: Vec<&str> = version.split('.').collect();
                 if parts.len() < 2 {
-                    return Err("Calver version must have at least YY.MM".into());
+                    return Err(VersionItError::VersionParse("Calver version must have at least YY.MM".to_string()));
                 }
                 let year = parts[0].parse()?;
                 let month = parts[1].parse()?;
                 let day = parts.get(2).map(|s| s.parse()).unwrap_or(Ok(1))?;
                 VersionType::Calver { year, month, day }
             }
-            "timestamp" => {
+            Scheme::Timestamp => {
                 if version.is_empty() {
                     VersionType::Timestamp(Self::current_timestamp())
                 } else {
                     VersionType::Timestamp(version.to_string())
                 }
             }
-            "commit" => {
+            Scheme::Commit => {
                 if version.is_empty() {
                     VersionType::Commit(Self::current_commit()?)
                 } else {
                     VersionType::Commit(version.to_string())
                 }
             }
-            "build" => {
+            Scheme::Build => {
                 let parts: Vec<&str> = version.split('.').collect();
                 if parts.len() != 4 {
-                    return Err("Build version must be in format major.minor.patch.build".into());
+                    return Err(VersionItError::VersionParse("Build version must be in format major.minor.patch.build".to_string()));
                 }
                 let major = parts[0].parse()?;
                 let minor = parts[1].parse()?;
@@ -72,28 +352,31 @@ impl VersionInfo {
                 let build = parts[3].parse()?;
                 VersionType::Build { major, minor, patch, build }
             }
-            "monotonic" => {
+            Scheme::Numeric => {
+                let parts = version.split('.').map(|part| part.parse()).collect::<Result<Vec<u64>, _>>()
+                    .map_err(|_| VersionItError::VersionParse(format!("Numeric version must be dot-separated numbers, got '{}'", version)))?;
+                VersionType::Numeric(parts)
+            }
+            Scheme::Monotonic => {
                 let num: u64 = version.parse()?;
                 VersionType::Monotonic(num)
             }
-            "datetime" => {
+            Scheme::Datetime => {
                 if version.is_empty() {
                     VersionType::Datetime(Self::current_datetime())
                 } else {
                     VersionType::Datetime(version.to_string())
                 }
             }
-            "pattern" => {
-                VersionType::Pattern(version.to_string())
-            }
-            "semantic-commit" => {
+            Scheme::Pattern => VersionType::Pattern(PatternVersion::infer(version)?),
+            Scheme::SemanticCommit => {
                 if version.is_empty() {
                     let commit_count = Self::current_commit_count().unwrap_or(0);
                     VersionType::SemanticCommit { major: 0, minor: 0, commit_count }
                 } else {
                     let parts: Vec<&str> = version.split('.').collect();
                     if parts.len() != 3 {
-                        return Err("Semantic-commit version must be in format major.minor.commit_count".into());
+                        return Err(VersionItError::VersionParse("Semantic-commit version must be in format major.minor.commit_count".to_string()));
                     }
                     let major = parts[0].parse()?;
                     let minor = parts[1].parse()?;
@@ -101,13 +384,147 @@ impl VersionInfo {
                     VersionType::SemanticCommit { major, minor, commit_count }
                 }
             }
-            _ => VersionType::Semantic(Version::parse(version)?),
+            Scheme::Semantic => VersionType::Semantic(Version::parse(version)?),
         };
-        Ok(Self {
-            scheme: scheme.to_string(),
-            version: version_type,
+        Ok(Self::from_parts(scheme, version_type, channel))
+    }
+
+    fn from_parts(scheme: Scheme, version: VersionType, channel: Option<String>) -> Self {
+        Self {
+            scheme,
+            version,
             channel,
-        })
+            monotonic_step: 1,
+            channel_separator: "-".to_string(),
+            branch: None,
+        }
+    }
+
+    /// Creates a new VersionInfo, parsing calver versions in the given format.
+    ///
+    /// With `CalverFormat::IsoWeek` a calver version is `YYYY.WW`; an empty version
+    /// starts at the current ISO week. Other schemes ignore the format.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the VersionInfo or an error if parsing fails.
+    pub fn new_with_calver_format(version: &str, scheme: Scheme, format: CalverFormat, channel: Option<String>) -> Result<Self, VersionItError> {
+        if scheme != Scheme::Calver || format == CalverFormat::YearMonthDay {
+            return Self::new(version, scheme, channel);
+        }
+        let (year, week) = if version.is_empty() {
+            Self::iso_week_of(crate::utils::now().date_naive())
+        } else {
+            let version = version.split_once('-').map_or(version, |(week, _)| week);
+            let (year, week) = version.split_once('.')
+                .ok_or_else(|| VersionItError::VersionParse("Calver week version must be in format YYYY.WW".to_string()))?;
+            (year.parse()?, week.parse()?)
+        };
+        if NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).is_none() {
+            return Err(VersionItError::VersionParse(format!("Week {} does not exist in {}", week, year)));
+        }
+        Ok(Self::from_parts(scheme, VersionType::CalverWeek { year, week }, channel))
+    }
+
+    /// Creates a new pattern scheme VersionInfo from a `version-pattern` template.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The current version, rendered from the template (empty to start fresh).
+    /// * `template` - The template, e.g. `{major}.{minor}.{date:%Y%m%d}`.
+    /// * `channel` - Optional channel name.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the VersionInfo or an error if the version does not match.
+    pub fn new_with_pattern(version: &str, template: &str, channel: Option<String>) -> Result<Self, VersionItError> {
+        let pattern = PatternVersion::parse(template, version)?;
+        Ok(Self::from_parts(Scheme::Pattern, VersionType::Pattern(pattern), channel))
+    }
+
+    /// Returns the ISO 8601 week-numbering year and week of a date. Early January
+    /// dates can belong to the last week of the previous year.
+    pub fn iso_week_of(date: NaiveDate) -> (i32, u32) {
+        let week = date.iso_week();
+        (week.year(), week.week())
+    }
+
+    /// Returns the ISO week after the given one, rolling over after week 52 or 53.
+    fn next_iso_week(year: i32, week: u32) -> (i32, u32) {
+        NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)
+            .and_then(|monday| monday.checked_add_days(Days::new(7)))
+            .map(Self::iso_week_of)
+            .unwrap_or((year + 1, 1))
+    }
+
+    /// Creates a new VersionInfo from a scheme name such as `calver`.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the VersionInfo, or an error for an unknown scheme or a
+    /// version that doesn't parse.
+    pub fn new_with_scheme_name(version: &str, scheme: &str, channel: Option<String>) -> Result<Self, VersionItError> {
+        Self::new(version, scheme.parse()?, channel)
+    }
+
+    /// Parses a version without knowing its scheme, e.g. from an arbitrary tag.
+    ///
+    /// Schemes are tried in this order, and the first that parses wins:
+    /// semantic (`1.2.3`), build (`1.2.3.4`), calver (`25.10.01`, with a valid month and
+    /// day) and monotonic (`42`). Ambiguous input such as `25.10.1` is therefore semantic;
+    /// calver is only picked when semver rejects the string, e.g. for a zero-padded day.
+    /// Free-form schemes (timestamp, commit, datetime, pattern) are never detected.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The version string to parse.
+    ///
+    /// # Returns
+    ///
+    /// The VersionInfo with the detected scheme, or None if no scheme matches.
+    pub fn detect(version: &str) -> Option<Self> {
+        [Scheme::Semantic, Scheme::Build, Scheme::Calver, Scheme::Monotonic]
+            .into_iter()
+            .filter_map(|scheme| Self::new(version, scheme, None).ok())
+            .find(|v| match v.version {
+                VersionType::Calver { month, day, .. } => (1..=12).contains(&month) && (1..=31).contains(&day),
+                _ => true,
+            })
+    }
+
+    /// Compares two versions of the same scheme, ignoring the channel.
+    ///
+    /// Semantic versions follow semver precedence; timestamps and datetimes compare
+    /// chronologically.
+    ///
+    /// # Returns
+    ///
+    /// The ordering, or None when the versions can't be ordered: different schemes, or
+    /// commit hashes and patterns, which have no order.
+    pub fn compare(&self, other: &VersionInfo) -> Option<Ordering> {
+        match (&self.version, &other.version) {
+            (VersionType::Semantic(a), VersionType::Semantic(b)) => Some(a.cmp(b)),
+            (VersionType::Calver { year, month, day }, VersionType::Calver { year: y, month: m, day: d }) => Some((year, month, day).cmp(&(y, m, d))),
+            (VersionType::CalverWeek { year, week }, VersionType::CalverWeek { year: y, week: w }) => Some((year, week).cmp(&(y, w))),
+            (VersionType::Timestamp(a), VersionType::Timestamp(b)) => Some(a.cmp(b)),
+            (VersionType::Build { major, minor, patch, build }, VersionType::Build { major: ma, minor: mi, patch: pa, build: bu }) => {
+                Some((major, minor, patch, build).cmp(&(ma, mi, pa, bu)))
+            }
+            // Missing components count as zero, so 1.2 equals 1.2.0
+            (VersionType::Numeric(a), VersionType::Numeric(b)) => {
+                let component = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+                Some((0..a.len().max(b.len())).map(|i| component(a, i).cmp(&component(b, i))).find(|o| o.is_ne()).unwrap_or(Ordering::Equal))
+            }
+            (VersionType::Monotonic(a), VersionType::Monotonic(b)) => Some(a.cmp(b)),
+            (VersionType::Datetime(a), VersionType::Datetime(b)) => Some(a.cmp(b)),
+            (VersionType::SemanticCommit { major, minor, commit_count }, VersionType::SemanticCommit { major: ma, minor: mi, commit_count: co }) => {
+                Some((major, minor, commit_count).cmp(&(ma, mi, co)))
+            }
+            (VersionType::Pattern(a), VersionType::Pattern(b)) if a.template == b.template => {
+                Some((a.major, a.minor, a.patch, a.build).cmp(&(b.major, b.minor, b.patch, b.build)))
+            }
+            _ => None,
+        }
     }
 
     /// Bumps the major version component.
@@ -118,6 +535,10 @@ impl VersionInfo {
                 *month = 1;
                 *day = 1;
             }
+            VersionType::CalverWeek { year, week } => {
+                *year += 1;
+                *week = 1;
+            }
             VersionType::Semantic(v) => {
                 v.major += 1;
                 v.minor = 0;
@@ -132,9 +553,16 @@ impl VersionInfo {
                 *minor = 0;
                 *patch = 0;
             }
-            VersionType::Monotonic(n) => *n += 1,
+            VersionType::Numeric(parts) => bump_numeric(parts, 0),
+            VersionType::Monotonic(n) => *n += self.monotonic_step,
             VersionType::Datetime(s) => *s = Self::current_datetime(),
-            VersionType::Pattern(s) => *s = format!("{}-updated", s),
+            VersionType::Pattern(p) => {
+                p.major += 1;
+                p.minor = 0;
+                p.patch = 0;
+                p.build += 1;
+                p.render();
+            }
             VersionType::SemanticCommit { major, minor, commit_count } => {
                 *major += 1;
                 *minor = 0;
@@ -150,6 +578,7 @@ impl VersionInfo {
                 *month += 1;
                 *day = 1;
             }
+            VersionType::CalverWeek { year, week } => (*year, *week) = Self::next_iso_week(*year, *week),
             VersionType::Semantic(v) => {
                 v.minor += 1;
                 v.patch = 0;
@@ -162,9 +591,15 @@ impl VersionInfo {
                 *minor += 1;
                 *patch = 0;
             }
-            VersionType::Monotonic(n) => *n += 1,
+            VersionType::Numeric(parts) => bump_numeric(parts, 1),
+            VersionType::Monotonic(n) => *n += self.monotonic_step,
             VersionType::Datetime(s) => *s = Self::current_datetime(),
-            VersionType::Pattern(s) => *s = format!("{}-updated", s),
+            VersionType::Pattern(p) => {
+                p.minor += 1;
+                p.patch = 0;
+                p.build += 1;
+                p.render();
+            }
             VersionType::SemanticCommit { minor, commit_count, .. } => {
                 *minor += 1;
                 *commit_count = Self::current_commit_count().unwrap_or(*commit_count);
@@ -178,6 +613,8 @@ impl VersionInfo {
             VersionType::Calver { day, .. } => {
                 *day += 1;
             }
+            // Weeks are the smallest component, so patch also moves to the next week
+            VersionType::CalverWeek { year, week } => (*year, *week) = Self::next_iso_week(*year, *week),
             VersionType::Semantic(v) => {
                 v.patch += 1;
                 v.pre = Prerelease::EMPTY;
@@ -189,15 +626,203 @@ impl VersionInfo {
                 *patch += 1;
                 *build = 0; // reset build on patch bump?
             }
-            VersionType::Monotonic(n) => *n += 1,
+            VersionType::Numeric(parts) => bump_numeric(parts, 2),
+            VersionType::Monotonic(n) => *n += self.monotonic_step,
             VersionType::Datetime(s) => *s = Self::current_datetime(),
-            VersionType::Pattern(s) => *s = format!("{}-updated", s),
+            VersionType::Pattern(p) => {
+                p.patch += 1;
+                p.build += 1;
+                p.render();
+            }
             VersionType::SemanticCommit { commit_count, .. } => {
                 *commit_count = Self::current_commit_count().unwrap_or(*commit_count);
             }
         }
     }
 
+    /// Bumps the component at `index` (0 for major) and resets the ones after it.
+    ///
+    /// Numeric versions have as many components as they have parts; build versions
+    /// have four, the last being the build number.
+    ///
+    /// # Returns
+    ///
+    /// An error if the scheme has no numbered components or the version has no
+    /// component at `index`.
+    pub fn bump_index(&mut self, index: usize) -> Result<(), VersionItError> {
+        let len = match &self.version {
+            VersionType::Numeric(parts) => parts.len(),
+            VersionType::Build { .. } => 4,
+            _ => return Err(VersionItError::VersionParse(format!("The {} scheme has no numbered components", self.scheme))),
+        };
+        if index >= len {
+            return Err(VersionItError::VersionParse(format!("Version {} has no component {} (it has {})", self, index, len)));
+        }
+        match &mut self.version {
+            VersionType::Numeric(parts) => bump_numeric(parts, index),
+            VersionType::Build { build, .. } if index == 3 => *build += 1,
+            _ => match index {
+                0 => self.bump_major(),
+                1 => self.bump_minor(),
+                _ => self.bump_patch(),
+            },
+        }
+        Ok(())
+    }
+
+    /// Sets the major version component, resetting the lower components.
+    ///
+    /// # Returns
+    ///
+    /// An error if the versioning scheme has no major component.
+    pub fn set_major(&mut self, value: u64) -> Result<(), VersionItError> {
+        match &mut self.version {
+            VersionType::Semantic(v) => {
+                v.major = value;
+                v.minor = 0;
+                v.patch = 0;
+                v.pre = Prerelease::EMPTY;
+                v.build = BuildMetadata::EMPTY;
+            }
+            VersionType::Calver { year, month, day } => {
+                *year = value.try_into()?;
+                *month = 1;
+                *day = 1;
+            }
+            VersionType::CalverWeek { year, week } => {
+                *year = value.try_into()?;
+                *week = 1;
+            }
+            VersionType::Build { major, minor, patch, .. } => {
+                *major = value.try_into()?;
+                *minor = 0;
+                *patch = 0;
+            }
+            VersionType::SemanticCommit { major, minor, .. } => {
+                *major = value.try_into()?;
+                *minor = 0;
+            }
+            VersionType::Numeric(parts) => set_numeric(parts, 0, value)?,
+            VersionType::Pattern(p) => {
+                p.major = value;
+                p.minor = 0;
+                p.patch = 0;
+                p.render();
+            }
+            _ => return Err(VersionItError::VersionParse(format!("The {} scheme has no major component", self.scheme))),
+        }
+        Ok(())
+    }
+
+    /// Sets the minor version component, resetting the lower components.
+    ///
+    /// # Returns
+    ///
+    /// An error if the versioning scheme has no minor component.
+    pub fn set_minor(&mut self, value: u64) -> Result<(), VersionItError> {
+        match &mut self.version {
+            VersionType::Semantic(v) => {
+                v.minor = value;
+                v.patch = 0;
+                v.pre = Prerelease::EMPTY;
+                v.build = BuildMetadata::EMPTY;
+            }
+            VersionType::Calver { year, month, day } => {
+                let value: u32 = value.try_into()?;
+                if !calver_date_exists(*year, value, 1) {
+                    return Err(VersionItError::VersionParse(format!("Month {} does not exist", value)));
+                }
+                *month = value;
+                *day = 1;
+            }
+            VersionType::CalverWeek { year, week } => {
+                let value: u32 = value.try_into()?;
+                if NaiveDate::from_isoywd_opt(*year, value, Weekday::Mon).is_none() {
+                    return Err(VersionItError::VersionParse(format!("Week {} does not exist in {}", value, year)));
+                }
+                *week = value;
+            }
+            VersionType::Build { minor, patch, .. } => {
+                *minor = value.try_into()?;
+                *patch = 0;
+            }
+            VersionType::SemanticCommit { minor, .. } => {
+                *minor = value.try_into()?;
+            }
+            VersionType::Numeric(parts) => set_numeric(parts, 1, value)?,
+            VersionType::Pattern(p) => {
+                p.minor = value;
+                p.patch = 0;
+                p.render();
+            }
+            _ => return Err(VersionItError::VersionParse(format!("The {} scheme has no minor component", self.scheme))),
+        }
+        Ok(())
+    }
+
+    /// Sets the patch version component.
+    ///
+    /// # Returns
+    ///
+    /// An error if the versioning scheme has no patch component.
+    pub fn set_patch(&mut self, value: u64) -> Result<(), VersionItError> {
+        match &mut self.version {
+            VersionType::Semantic(v) => {
+                v.patch = value;
+                v.pre = Prerelease::EMPTY;
+                v.build = BuildMetadata::EMPTY;
+            }
+            VersionType::Calver { year, month, day } => {
+                let value: u32 = value.try_into()?;
+                if !calver_date_exists(*year, *month, value) {
+                    return Err(VersionItError::VersionParse(format!("Day {} does not exist in {}.{:02}", value, year, month)));
+                }
+                *day = value;
+            }
+            VersionType::Build { patch, .. } => {
+                *patch = value.try_into()?;
+            }
+            VersionType::Numeric(parts) => set_numeric(parts, 2, value)?,
+            VersionType::Pattern(p) => {
+                p.patch = value;
+                p.render();
+            }
+            _ => return Err(VersionItError::VersionParse(format!("The {} scheme has no patch component", self.scheme))),
+        }
+        Ok(())
+    }
+
+    /// Sets the increment used when bumping a monotonic version.
+    pub fn set_monotonic_step(&mut self, step: u64) {
+        self.monotonic_step = step;
+    }
+
+    /// Sets the separator placed between the version and the channel suffix,
+    /// e.g. `~` for Debian style `1.2.0~beta`.
+    pub fn set_channel_separator(&mut self, separator: &str) {
+        self.channel_separator = separator.to_string();
+    }
+
+    /// Sets the branch appended to calver versions. Other schemes ignore it.
+    pub fn set_branch(&mut self, branch: &str) {
+        self.branch = Some(branch.to_string());
+    }
+
+    /// Sets a monotonic version to an explicit value.
+    ///
+    /// # Returns
+    ///
+    /// An error if the version is not monotonic.
+    pub fn set_monotonic(&mut self, value: u64) -> Result<(), VersionItError> {
+        match &mut self.version {
+            VersionType::Monotonic(n) => {
+                *n = value;
+                Ok(())
+            }
+            _ => Err(VersionItError::VersionParse(format!("Cannot set a monotonic value on a {} version", self.scheme))),
+        }
+    }
+
     pub fn set_prerelease(&mut self, pre: &str) {
         if let VersionType::Semantic(v) = &mut self.version {
             v.pre = Prerelease::new(pre).unwrap_or(Prerelease::EMPTY);
@@ -211,35 +836,41 @@ impl VersionInfo {
     }
 
     fn current_timestamp() -> String {
-        let now: DateTime<Utc> = Utc::now();
+        let now: DateTime<Utc> = crate::utils::now();
         now.format("%Y%m%d%H%M%S").to_string()
     }
 
-    pub(crate) fn current_commit() -> Result<String, Box<dyn std::error::Error>> {
+    pub(crate) fn current_commit() -> Result<String, VersionItError> {
         let output = Command::new("git").args(["rev-parse", "--short", "HEAD"]).output()?;
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
         } else {
-            Err("Failed to get git commit".into())
+            Err(VersionItError::Git("Failed to get git commit".to_string()))
         }
     }
 
     fn current_datetime() -> String {
-        let now: DateTime<Utc> = Utc::now();
+        let now: DateTime<Utc> = crate::utils::now();
         now.format("%Y-%m-%dT%H:%M:%S").to_string()
     }
 
-    fn current_commit_count() -> Result<u32, Box<dyn std::error::Error>> {
+    fn current_commit_count() -> Result<u32, VersionItError> {
         let output = Command::new("git").args(["rev-list", "--count", "HEAD"]).output()?;
         if output.status.success() {
             let count: u32 = String::from_utf8_lossy(&output.stdout).trim().parse().unwrap_or(0);
             Ok(count)
         } else {
-            Err("Failed to get commit count".into())
+            Err(VersionItError::Git("Failed to get commit count".to_string()))
         }
     }
 
+    /// Returns the version of the compiler that built this crate, falling back
+    /// to the rustc on PATH when it could not be captured at build time.
     pub fn rustc_version() -> String {
+        let build_time = env!("VERSION_IT_RUSTC_VERSION");
+        if !build_time.is_empty() {
+            return build_time.to_string();
+        }
         // Try to get rustc version
         if let Ok(output) = Command::new("rustc").args(["--version"]).output() {
             if output.status.success() {
@@ -257,12 +888,28 @@ impl VersionInfo {
         std::env::consts::ARCH.to_string()
     }
 
-    pub fn available_memory() -> String {
+    /// Returns the total and available system memory in bytes.
+    ///
+    /// The values are read once per process since querying the system is expensive.
+    fn memory_info() -> (u64, u64) {
+        use std::sync::OnceLock;
         use sysinfo::System;
-        let mut sys = System::new_all();
-        sys.refresh_all();
-        let total_memory = sys.total_memory();
-        let available_memory = sys.available_memory();
+        static MEMORY: OnceLock<(u64, u64)> = OnceLock::new();
+        *MEMORY.get_or_init(|| {
+            let mut sys = System::new();
+            sys.refresh_memory();
+            (sys.total_memory(), sys.available_memory())
+        })
+    }
+
+    /// Returns the available system memory in bytes.
+    pub fn available_memory_bytes() -> u64 {
+        Self::memory_info().1
+    }
+
+    /// Returns the system memory formatted as "X MB total, Y MB available".
+    pub fn available_memory_human() -> String {
+        let (total_memory, available_memory) = Self::memory_info();
         format!("{} MB total, {} MB available", total_memory / 1024 / 1024, available_memory / 1024 / 1024)
     }
 
@@ -271,55 +918,196 @@ impl VersionInfo {
     }
 }
 
+/// Bumps a numeric component and resets the ones after it; a missing component is left
+/// alone, see `VersionInfo::bump_index` for the checked form.
+fn bump_numeric(parts: &mut [u64], index: usize) {
+    if index < parts.len() {
+        parts[index] += 1;
+        parts[index + 1..].fill(0);
+    }
+}
+
+/// Whether a calver date exists; two-digit years such as `25` are read as 2025.
+fn calver_date_exists(year: u32, month: u32, day: u32) -> bool {
+    let year = if year < 100 { year + 2000 } else { year };
+    i32::try_from(year).is_ok_and(|year| NaiveDate::from_ymd_opt(year, month, day).is_some())
+}
+
+/// Sets a numeric component and resets the ones after it.
+fn set_numeric(parts: &mut [u64], index: usize, value: u64) -> Result<(), VersionItError> {
+    if index >= parts.len() {
+        return Err(VersionItError::VersionParse(format!("Numeric version has no component {} (it has {})", index, parts.len())));
+    }
+    parts[index] = value;
+    parts[index + 1..].fill(0);
+    Ok(())
+}
+
 #[cfg(test)]
+#[allow(clippy::items_after_test_module, clippy::len_zero)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_channel_separator() {
+        let mut v = VersionInfo::new("1.2.0", Scheme::Semantic, Some("beta".to_string())).unwrap();
+        v.set_channel_separator("~");
+        assert_eq!(v.to_string(), "1.2.0~beta.1");
+
+        let mut v = VersionInfo::new("1.2.0", Scheme::Semantic, Some("rc".to_string())).unwrap();
+        v.set_channel_separator(".");
+        assert_eq!(v.to_string(), "1.2.0.rc");
+    }
+
+    #[test]
+    fn test_build_metadata_follows_channel_suffix() {
+        let mut v = VersionInfo::new("1.2.3", Scheme::Semantic, Some("beta".to_string())).unwrap();
+        v.set_build("sha.abc123");
+        assert_eq!(v.to_string(), "1.2.3-beta.1+sha.abc123");
+    }
+
+    #[test]
+    fn test_scheme_names_round_trip() {
+        for scheme in Scheme::ALL {
+            assert_eq!(scheme.as_str().parse::<Scheme>().unwrap(), scheme);
+            assert_eq!(serde_yaml::to_string(&scheme).unwrap().trim(), scheme.as_str());
+        }
+        assert!("semver".parse::<Scheme>().is_err());
+        assert!(VersionInfo::new_with_scheme_name("1.2.3", "semver", None).is_err());
+        assert!(matches!(VersionInfo::new_with_scheme_name("42", "monotonic", None).unwrap().version, VersionType::Monotonic(42)));
+    }
+
+    #[test]
+    fn test_detect_scheme() {
+        let scheme = |version: &str| VersionInfo::detect(version).map(|v| v.scheme);
+        assert_eq!(scheme("1.2.3"), Some(Scheme::Semantic));
+        assert_eq!(scheme("1.2.3-rc.1"), Some(Scheme::Semantic));
+        assert_eq!(scheme("1.2.3.4"), Some(Scheme::Build));
+        assert_eq!(scheme("42"), Some(Scheme::Monotonic));
+        assert_eq!(scheme("abc"), None);
+    }
+
+    #[test]
+    fn test_detect_ambiguous_calver() {
+        // Valid semver wins over calver
+        assert_eq!(VersionInfo::detect("25.10.1").unwrap().scheme, Scheme::Semantic);
+        // A zero-padded day is not semver, so it must be calver
+        let v = VersionInfo::detect("25.10.01").unwrap();
+        assert_eq!(v.scheme, Scheme::Calver);
+        assert!(matches!(v.version, VersionType::Calver { year: 25, month: 10, day: 1 }));
+        assert_eq!(VersionInfo::detect("25.10").unwrap().scheme, Scheme::Calver);
+        // Not a month, so not calver either
+        assert!(VersionInfo::detect("25.13").is_none());
+    }
+
+    #[test]
+    fn test_iso_week_year_boundary() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // 2021-01-03 is a Sunday in the last ISO week of 2020, which has 53 weeks
+        assert_eq!(VersionInfo::iso_week_of(date(2021, 1, 3)), (2020, 53));
+        assert_eq!(VersionInfo::iso_week_of(date(2021, 1, 4)), (2021, 1));
+        // 2024-12-30 already belongs to week 1 of 2025
+        assert_eq!(VersionInfo::iso_week_of(date(2024, 12, 30)), (2025, 1));
+    }
+
+    #[test]
+    fn test_calver_week_bump_rolls_year() {
+        let week = |version: &str| VersionInfo::new_with_calver_format(version, Scheme::Calver, CalverFormat::IsoWeek, None).unwrap();
+
+        let mut v = week("2020.52");
+        v.bump_minor();
+        assert_eq!(v.to_string(), "2020.53");
+        v.bump_minor();
+        assert_eq!(v.to_string(), "2021.01");
+
+        // 2021 only has 52 weeks
+        let mut v = week("2021.52");
+        v.bump_patch();
+        assert_eq!(v.to_string(), "2022.01");
+
+        let mut v = week("2025.07");
+        v.bump_major();
+        assert_eq!(v.to_string(), "2026.01");
+        // 2026 starts on a Thursday, so it has a week 53
+        v.set_minor(53).unwrap();
+        assert_eq!(v.to_string(), "2026.53");
+        assert!(v.set_minor(54).is_err());
+        assert!(VersionInfo::new_with_calver_format("2021.53", Scheme::Calver, CalverFormat::IsoWeek, None).is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let semantic = |version: &str| VersionInfo::new(version, Scheme::Semantic, None).unwrap();
+        assert_eq!(semantic("1.2.3").compare(&semantic("1.10.0")), Some(Ordering::Less));
+        assert_eq!(semantic("1.3.0-rc.1").compare(&semantic("1.3.0")), Some(Ordering::Less));
+        assert_eq!(semantic("2.0.0").compare(&semantic("1.9.9")), Some(Ordering::Greater));
+
+        let calver = VersionInfo::new("25.10.01", Scheme::Calver, None).unwrap();
+        assert_eq!(calver.compare(&VersionInfo::new("25.9.30", Scheme::Calver, None).unwrap()), Some(Ordering::Greater));
+        assert_eq!(calver.compare(&semantic("1.2.3")), None);
+
+        let commit = VersionInfo::new("abc1234", Scheme::Commit, None).unwrap();
+        assert_eq!(commit.compare(&commit), None);
+    }
+
     #[test]
     fn test_bump_major() {
-        let mut v = VersionInfo::new("1.2.3", "semantic", None).unwrap();
+        let mut v = VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap();
         v.bump_major();
         assert_eq!(v.to_string(), "2.0.0");
     }
 
     #[test]
     fn test_bump_minor() {
-        let mut v = VersionInfo::new("1.2.3", "semantic", None).unwrap();
+        let mut v = VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap();
         v.bump_minor();
         assert_eq!(v.to_string(), "1.3.0");
     }
 
     #[test]
     fn test_bump_patch() {
-        let mut v = VersionInfo::new("1.2.3", "semantic", None).unwrap();
+        let mut v = VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap();
         v.bump_patch();
         assert_eq!(v.to_string(), "1.2.4");
     }
 
     #[test]
     fn test_calver_bump_minor() {
-        let mut v = VersionInfo::new("25.10.01", "calver", None).unwrap();
+        let mut v = VersionInfo::new("25.10.01", Scheme::Calver, None).unwrap();
         v.bump_minor();
         assert_eq!(v.to_string(), "25.11.01");
     }
 
     #[test]
     fn test_calver_bump_major() {
-        let mut v = VersionInfo::new("25.10.01", "calver", None).unwrap();
+        let mut v = VersionInfo::new("25.10.01", Scheme::Calver, None).unwrap();
         v.bump_major();
         assert_eq!(v.to_string(), "26.01.01");
     }
 
+    #[test]
+    fn test_calver_branch() {
+        let mut v = VersionInfo::new("25.10.01-main", Scheme::Calver, None).unwrap();
+        assert_eq!(v.to_string(), "25.10.01");
+        v.set_branch("main");
+        v.bump_minor();
+        assert_eq!(v.to_string(), "25.11.01-main");
+
+        let mut v = VersionInfo::new_with_calver_format("2025.41-dev", Scheme::Calver, CalverFormat::IsoWeek, None).unwrap();
+        v.set_branch("dev");
+        assert_eq!(v.to_string(), "2025.41-dev");
+    }
+
     #[test]
     fn test_timestamp_new() {
-        let v = VersionInfo::new("", "timestamp", None).unwrap();
+        let v = VersionInfo::new("", Scheme::Timestamp, None).unwrap();
         assert!(v.to_string().len() == 14); // YYYYMMDDHHMMSS
     }
 
     #[test]
     fn test_commit_new() {
         // This will fail if no git, but assume it's there
-        let v = VersionInfo::new("", "commit", None);
+        let v = VersionInfo::new("", Scheme::Commit, None);
         if let Ok(v) = v {
             assert!(v.to_string().len() > 0);
         }
@@ -327,61 +1115,61 @@ mod tests {
 
     #[test]
     fn test_versioninfo_new_semantic() {
-        let v = VersionInfo::new("1.2.3", "semantic", None).unwrap();
-        assert_eq!(v.scheme, "semantic");
+        let v = VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap();
+        assert_eq!(v.scheme, Scheme::Semantic);
         assert_eq!(v.to_string(), "1.2.3");
     }
 
     #[test]
     fn test_versioninfo_new_calver() {
-        let v = VersionInfo::new("25.10.01", "calver", None).unwrap();
-        assert_eq!(v.scheme, "calver");
+        let v = VersionInfo::new("25.10.01", Scheme::Calver, None).unwrap();
+        assert_eq!(v.scheme, Scheme::Calver);
         assert_eq!(v.to_string(), "25.10.01");
     }
 
     #[test]
     fn test_versioninfo_new_timestamp() {
-        let v = VersionInfo::new("20231005120000", "timestamp", None).unwrap();
-        assert_eq!(v.scheme, "timestamp");
+        let v = VersionInfo::new("20231005120000", Scheme::Timestamp, None).unwrap();
+        assert_eq!(v.scheme, Scheme::Timestamp);
         assert_eq!(v.to_string(), "20231005120000");
     }
 
     #[test]
     fn test_versioninfo_new_commit() {
-        let v = VersionInfo::new("abc123", "commit", None).unwrap();
-        assert_eq!(v.scheme, "commit");
+        let v = VersionInfo::new("abc123", Scheme::Commit, None).unwrap();
+        assert_eq!(v.scheme, Scheme::Commit);
         assert_eq!(v.to_string(), "abc123");
     }
 
     #[test]
     fn test_versioninfo_new_invalid_semantic() {
-        let result = VersionInfo::new("invalid", "semantic", None);
+        let result = VersionInfo::new("invalid", Scheme::Semantic, None);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_versioninfo_new_invalid_calver() {
-        let result = VersionInfo::new("25", "calver", None);
+        let result = VersionInfo::new("25", Scheme::Calver, None);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_set_prerelease() {
-        let mut v = VersionInfo::new("1.2.3", "semantic", None).unwrap();
+        let mut v = VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap();
         v.set_prerelease("alpha.1");
         assert_eq!(v.to_string(), "1.2.3-alpha.1");
     }
 
     #[test]
     fn test_set_build() {
-        let mut v = VersionInfo::new("1.2.3", "semantic", None).unwrap();
+        let mut v = VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap();
         v.set_build("build.1");
         assert_eq!(v.to_string(), "1.2.3+build.1");
     }
 
     #[test]
     fn test_set_prerelease_and_build() {
-        let mut v = VersionInfo::new("1.2.3", "semantic", None).unwrap();
+        let mut v = VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap();
         v.set_prerelease("beta");
         v.set_build("sha.123");
         assert_eq!(v.to_string(), "1.2.3-beta+sha.123");
@@ -389,42 +1177,82 @@ mod tests {
 
     #[test]
     fn test_versioninfo_new_datetime() {
-        let v = VersionInfo::new("2024-10-06T14:30:00", "datetime", None).unwrap();
-        assert_eq!(v.scheme, "datetime");
+        let v = VersionInfo::new("2024-10-06T14:30:00", Scheme::Datetime, None).unwrap();
+        assert_eq!(v.scheme, Scheme::Datetime);
         assert_eq!(v.to_string(), "2024-10-06T14:30:00");
     }
 
     #[test]
     fn test_versioninfo_new_pattern() {
-        let v = VersionInfo::new("v1.0.0-snapshot", "pattern", None).unwrap();
-        assert_eq!(v.scheme, "pattern");
+        let v = VersionInfo::new("v1.0.0-snapshot", Scheme::Pattern, None).unwrap();
+        assert_eq!(v.scheme, Scheme::Pattern);
         assert_eq!(v.to_string(), "v1.0.0-snapshot");
     }
 
     #[test]
     fn test_build_bump_major() {
-        let mut v = VersionInfo::new("1.2.3.4", "build", None).unwrap();
+        let mut v = VersionInfo::new("1.2.3.4", Scheme::Build, None).unwrap();
         v.bump_major();
         assert_eq!(v.to_string(), "2.0.0.4");
     }
 
     #[test]
     fn test_build_bump_minor() {
-        let mut v = VersionInfo::new("1.2.3.4", "build", None).unwrap();
+        let mut v = VersionInfo::new("1.2.3.4", Scheme::Build, None).unwrap();
         v.bump_minor();
         assert_eq!(v.to_string(), "1.3.0.4");
     }
 
     #[test]
     fn test_build_bump_patch() {
-        let mut v = VersionInfo::new("1.2.3.4", "build", None).unwrap();
+        let mut v = VersionInfo::new("1.2.3.4", Scheme::Build, None).unwrap();
         v.bump_patch();
         assert_eq!(v.to_string(), "1.2.4.0");
     }
 
+    #[test]
+    fn test_numeric_round_trip() {
+        for version in ["3.1", "1.2.3", "1.0.4.2.7"] {
+            assert_eq!(VersionInfo::new(version, Scheme::Numeric, None).unwrap().to_string(), version);
+        }
+        assert!(VersionInfo::new("1.x.3", Scheme::Numeric, None).is_err());
+        assert!(VersionInfo::new("", Scheme::Numeric, None).is_err());
+    }
+
+    #[test]
+    fn test_numeric_bump() {
+        let mut v = VersionInfo::new("1.0.4.2.7", Scheme::Numeric, None).unwrap();
+        v.bump_minor();
+        assert_eq!(v.to_string(), "1.1.0.0.0");
+        v.bump_index(4).unwrap();
+        assert_eq!(v.to_string(), "1.1.0.0.1");
+        v.bump_index(3).unwrap();
+        assert_eq!(v.to_string(), "1.1.0.1.0");
+        assert!(v.bump_index(5).is_err());
+
+        let mut v = VersionInfo::new("3.1", Scheme::Numeric, None).unwrap();
+        v.bump_patch();
+        assert_eq!(v.to_string(), "3.1");
+        assert!(v.bump_index(2).is_err());
+        assert!(v.set_patch(1).is_err());
+        v.bump_major();
+        assert_eq!(v.to_string(), "4.0");
+
+        let mut v = VersionInfo::new("1.2.3.4", Scheme::Build, None).unwrap();
+        v.bump_index(3).unwrap();
+        assert_eq!(v.to_string(), "1.2.3.5");
+        assert!(VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap().bump_index(0).is_err());
+
+        let a = VersionInfo::new("1.2", Scheme::Numeric, None).unwrap();
+        let b = VersionInfo::new("1.2.0", Scheme::Numeric, None).unwrap();
+        let c = VersionInfo::new("1.2.0.1", Scheme::Numeric, None).unwrap();
+        assert_eq!(a.compare(&b), Some(Ordering::Equal));
+        assert_eq!(a.compare(&c), Some(Ordering::Less));
+    }
+
     #[test]
     fn test_monotonic_bump() {
-        let mut v = VersionInfo::new("42", "monotonic", None).unwrap();
+        let mut v = VersionInfo::new("42", Scheme::Monotonic, None).unwrap();
         v.bump_major();
         assert_eq!(v.to_string(), "43");
         v.bump_minor();
@@ -433,9 +1261,88 @@ mod tests {
         assert_eq!(v.to_string(), "45");
     }
 
+    #[test]
+    fn test_monotonic_bump_with_step() {
+        let mut v = VersionInfo::new("100", Scheme::Monotonic, None).unwrap();
+        v.set_monotonic_step(10);
+        v.bump_patch();
+        assert_eq!(v.to_string(), "110");
+        v.bump_major();
+        assert_eq!(v.to_string(), "120");
+    }
+
+    #[test]
+    fn test_monotonic_default_step() {
+        let mut v = VersionInfo::new("100", Scheme::Monotonic, None).unwrap();
+        assert_eq!(v.monotonic_step, 1);
+        v.bump_patch();
+        assert_eq!(v.to_string(), "101");
+    }
+
+    #[test]
+    fn test_set_monotonic() {
+        let mut v = VersionInfo::new("42", Scheme::Monotonic, None).unwrap();
+        v.set_monotonic(500).unwrap();
+        assert_eq!(v.to_string(), "500");
+
+        let mut v = VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap();
+        assert!(v.set_monotonic(500).is_err());
+    }
+
+    #[test]
+    fn test_set_major_resets_lower_components() {
+        let mut v = VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap();
+        v.set_major(2).unwrap();
+        assert_eq!(v.to_string(), "2.0.0");
+    }
+
+    #[test]
+    fn test_set_minor_and_patch() {
+        let mut v = VersionInfo::new("1.2.3", Scheme::Semantic, None).unwrap();
+        v.set_minor(5).unwrap();
+        assert_eq!(v.to_string(), "1.5.0");
+        v.set_patch(7).unwrap();
+        assert_eq!(v.to_string(), "1.5.7");
+    }
+
+    #[test]
+    fn test_set_components_calver() {
+        let mut v = VersionInfo::new("25.10.15", Scheme::Calver, None).unwrap();
+        v.set_minor(12).unwrap();
+        assert_eq!(v.to_string(), "25.12.01");
+        assert!(v.set_minor(13).is_err());
+        assert!(v.set_patch(32).is_err());
+        v.set_minor(2).unwrap();
+        assert!(v.set_patch(29).is_err());
+        let mut leap = VersionInfo::new("2024.02.01", Scheme::Calver, None).unwrap();
+        leap.set_patch(29).unwrap();
+        assert_eq!(leap.to_string(), "2024.02.29");
+    }
+
+    #[test]
+    fn test_set_components_unsupported_scheme() {
+        let mut v = VersionInfo::new("20231005120000", Scheme::Timestamp, None).unwrap();
+        assert!(v.set_major(1).is_err());
+        assert!(v.set_minor(1).is_err());
+        assert!(v.set_patch(1).is_err());
+    }
+
+    #[test]
+    fn test_memory_representations_agree() {
+        let human = VersionInfo::available_memory_human();
+        assert!(human.ends_with("MB available"));
+        let available_mb = VersionInfo::available_memory_bytes() / 1024 / 1024;
+        assert!(human.contains(&format!(", {} MB available", available_mb)));
+    }
+
+    #[test]
+    fn test_rustc_version_known() {
+        assert!(VersionInfo::rustc_version().starts_with("rustc "));
+    }
+
     #[test]
     fn test_datetime_bump() {
-        let mut v = VersionInfo::new("2024-10-06T14:30:00", "datetime", None).unwrap();
+        let mut v = VersionInfo::new("2024-10-06T14:30:00", Scheme::Datetime, None).unwrap();
         let original = v.to_string();
         v.bump_major();
         assert_ne!(v.to_string(), original); // should update to current time
@@ -443,21 +1350,50 @@ mod tests {
 
     #[test]
     fn test_pattern_bump() {
-        let mut v = VersionInfo::new("v1.0.0", "pattern", None).unwrap();
+        let mut v = VersionInfo::new("v1.0.0-snapshot", Scheme::Pattern, None).unwrap();
+        v.bump_minor();
+        assert_eq!(v.to_string(), "v1.1.0-snapshot");
+        v.bump_major();
+        assert_eq!(v.to_string(), "v2.0.0-snapshot");
+
+        // Too large for a counter, so the first number stays literal
+        let mut v = VersionInfo::new("v99999999999999999999999-r4", Scheme::Pattern, None).unwrap();
+        v.bump_patch();
+        assert_eq!(v.to_string(), "v99999999999999999999999-r4");
         v.bump_major();
-        assert_eq!(v.to_string(), "v1.0.0-updated");
+        assert_eq!(v.to_string(), "v99999999999999999999999-r5");
+    }
+
+    #[test]
+    fn test_pattern_placeholders() {
+        let mut v = VersionInfo::new_with_pattern("3.4-b17", "{major}.{minor}-b{build}", None).unwrap();
+        v.bump_patch();
+        assert_eq!(v.to_string(), "3.4-b18");
+        v.bump_minor();
+        assert_eq!(v.to_string(), "3.5-b19");
+
+        let mut dated = VersionInfo::new_with_pattern("1.0.20240101", "{major}.{minor}.{date:%Y%m%d}", None).unwrap();
+        dated.bump_minor();
+        assert_eq!(dated.to_string(), format!("1.1.{}", crate::utils::now().format("%Y%m%d")));
+
+        let braces = VersionInfo::new_with_pattern("", "{{{major}}}", None).unwrap();
+        assert_eq!(braces.to_string(), "{0}");
+
+        assert!(VersionInfo::new_with_pattern("1.2", "{major}.{minro}", None).is_err());
+        assert!(VersionInfo::new_with_pattern("1.2", "{major}-{minor}", None).is_err());
+        assert!(VersionInfo::new_with_pattern("1.2", "{major}.{minor}}", None).is_err());
     }
 
     #[test]
     fn test_semantic_commit_new() {
-        let v = VersionInfo::new("1.23.456", "semantic-commit", None).unwrap();
-        assert_eq!(v.scheme, "semantic-commit");
+        let v = VersionInfo::new("1.23.456", Scheme::SemanticCommit, None).unwrap();
+        assert_eq!(v.scheme, Scheme::SemanticCommit);
         assert_eq!(v.to_string(), "1.23.456");
     }
 
     #[test]
     fn test_semantic_commit_bump_major() {
-        let mut v = VersionInfo::new("1.23.456", "semantic-commit", None).unwrap();
+        let mut v = VersionInfo::new("1.23.456", Scheme::SemanticCommit, None).unwrap();
         // Since we can't mock git, it will use current or fallback
         v.bump_major();
         // Assuming current commit count is 456 or more, but hard to test without mock
@@ -472,7 +1408,7 @@ mod tests {
 
     #[test]
     fn test_semantic_commit_bump_minor() {
-        let mut v = VersionInfo::new("1.23.456", "semantic-commit", None).unwrap();
+        let mut v = VersionInfo::new("1.23.456", Scheme::SemanticCommit, None).unwrap();
         v.bump_minor();
         if let VersionType::SemanticCommit { major, minor, .. } = v.version {
             assert_eq!(major, 1);
@@ -486,44 +1422,57 @@ mod tests {
 impl fmt::Display for VersionInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let base_version = match &self.version {
-            VersionType::Calver { year, month, day } => format!("{:02}.{:02}.{:02}", year, month, day),
-            VersionType::Semantic(v) => v.to_string(),
+            VersionType::Calver { year, month, day } => match self.branch {
+                Some(ref branch) => format!("{:02}.{:02}.{:02}-{}", year, month, day, branch),
+                None => format!("{:02}.{:02}.{:02}", year, month, day),
+            },
+            VersionType::CalverWeek { year, week } => match self.branch {
+                Some(ref branch) => format!("{:04}.{:02}-{}", year, week, branch),
+                None => format!("{:04}.{:02}", year, week),
+            },
+            // Build metadata is appended after the channel suffix below
+            VersionType::Semantic(v) => Version { build: BuildMetadata::EMPTY, ..v.clone() }.to_string(),
             VersionType::Timestamp(s) => s.clone(),
             VersionType::Commit(s) => s.clone(),
             VersionType::Build { major, minor, patch, build } => format!("{}.{}.{}.{}", major, minor, patch, build),
+            VersionType::Numeric(parts) => parts.iter().map(|n| n.to_string()).collect::<Vec<_>>().join("."),
             VersionType::Monotonic(n) => n.to_string(),
             VersionType::Datetime(s) => s.clone(),
-            VersionType::Pattern(s) => s.clone(),
+            VersionType::Pattern(p) => p.rendered.clone(),
             VersionType::SemanticCommit { major, minor, commit_count } => format!("{}.{}.{}", major, minor, commit_count),
         };
 
+        let sep = &self.channel_separator;
         let version_str = if let Some(ref channel) = self.channel {
             match channel.as_str() {
                 "stable" => base_version,
                 "beta" => {
                     if let VersionType::Semantic(ref v) = self.version {
                         if v.pre.is_empty() {
-                            format!("{}-beta.1", base_version)
+                            format!("{}{}beta.1", base_version, sep)
                         } else {
                             base_version
                         }
                     } else {
-                        format!("{}-beta", base_version)
+                        format!("{}{}beta", base_version, sep)
                     }
                 }
                 "nightly" => {
                     if matches!(self.version, VersionType::Timestamp(_) | VersionType::Commit(_)) {
                         base_version
                     } else {
-                        format!("{}-nightly", base_version)
+                        format!("{}{}nightly", base_version, sep)
                     }
                 }
-                _ => format!("{}-{}", base_version, channel),
+                _ => format!("{}{}{}", base_version, sep, channel),
             }
         } else {
             base_version
         };
 
-        write!(f, "{}", version_str)
+        match self.version {
+            VersionType::Semantic(ref v) if !v.build.is_empty() => write!(f, "{}+{}", version_str, v.build),
+            _ => write!(f, "{}", version_str),
+        }
     }
-}
\ No newline at end of file
+}